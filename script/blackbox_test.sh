@@ -8,6 +8,8 @@ test_file_names="
   test_ignore
   test_sweep
   test_burn
+  test_restore
+  test_undo
   test_cron
 "
 
@@ -33,6 +33,22 @@ class TestConfig < TestWithBasicSetup
       'sweep.moratorium' => ['0minute', '60minutes', '0hour', '24hours', '0day', '7days', '0week', '4weeks'],
       'sweep.period'     => ['daily', 'weekly'],
       'sweep.time'       => ['00:00', '23:59'],
+      'sweep.keep_git_tracked' => ['true', 'false'],
+      'sweep.timestamp'          => ['atime', 'mtime', 'ctime', 'newest'],
+      'sweep.include_hidden'     => ['true', 'false'],
+      'sweep.hidden_exclusions'  => ['.git', '.git,.kic,.DS_Store'],
+      'sweep.one_file_system'    => ['true', 'false'],
+      'sweep.empty_dirs'         => ['false', 'true'],
+      'sweep.empty_dirs_min_age' => ['3days', '1 week'],
+      'sweep.keep_marker'        => ['KEEP', '.kickeep'],
+      'sweep.min_size'           => ['1MiB', '100KB'],
+      'sweep.max_size'           => ['1GiB', '500MB'],
+      'sweep.max_total_bytes'    => ['10GiB', '1TB'],
+      'sweep.buckets'            => ['"images: png, image/*"', '"a: zip; b: application/pdf"'],
+      'rules.keep_larger_than'   => ['1GiB', '500MB', '1024'],
+      'rules.sweep_extensions'   => ['part', '.part,crdownload'],
+      'rules.keep_owners'        => ['0', '0,1000'],
+      'rules.sweep_mode'         => ['002', '0111'],
     }
     kvs.each do |key, values|
       values.each do |value|
@@ -48,6 +64,22 @@ class TestConfig < TestWithBasicSetup
       'sweep.moratorium' => ['1second', '-1minute', '-1hour', '-1day', '-1week', '1month'],
       'sweep.period'     => ['hourly', 'monthly'],
       'sweep.time'       => ['24:00', '00:00:00'],
+      'sweep.keep_git_tracked' => ['yes', 'no'],
+      'sweep.timestamp'          => ['btime', 'oldest'],
+      'sweep.include_hidden'     => ['yes', 'no'],
+      'sweep.hidden_exclusions'  => ['git', '.a/.b'],
+      'sweep.one_file_system'    => ['yes', 'no'],
+      'sweep.empty_dirs'         => ['yes', 'no'],
+      'sweep.empty_dirs_min_age' => ['1month', '1 year'],
+      'sweep.keep_marker'        => ['a/b', '..'],
+      'sweep.min_size'           => ['1KiBs', '-1B'],
+      'sweep.max_size'           => ['1PiB', 'GB'],
+      'sweep.max_total_bytes'    => ['1.5TB', '-1GB'],
+      'sweep.buckets'            => ['images', '"a/b: png"'],
+      'rules.keep_larger_than'   => ['-1GiB', '1PiB', 'GiB'],
+      'rules.sweep_extensions'   => [',', 'a/b'],
+      'rules.keep_owners'        => ['root', '-1'],
+      'rules.sweep_mode'         => ['0', '8', '10000'],
     }
     kvs.each do |key, values|
       values.each do |value|
@@ -6,6 +6,7 @@ class TestIgnore < TestWithBasicSetup
   @@command_refresh = 'ignore refresh'
   @@command_current = 'ignore current'
   @@command_clear   = 'ignore clear'
+  @@command_check   = 'ignore check'
 
   def test_config_add_should_display_usage
     exit_status, is_usage = output_usage?(@@command_add)
@@ -94,6 +95,14 @@ class TestIgnore < TestWithBasicSetup
     assert_false File.open(IGNORE_FILE, &:read).include?(expected_line)
   end
 
+  def test_config_refresh_should_keep_pinned_entries_and_report_removed_ones
+    File.write(IGNORE_FILE, "+./non_existing_file1\n./non_existing_file2\n")
+
+    output = exec(@@command_refresh)
+    assert_true output.include?('Remove "./non_existing_file2"')
+    assert_equal "+./non_existing_file1\n", File.open(IGNORE_FILE, &:read)
+  end
+
   def test_config_current_should_replace_ignore_file_with_new_one_mirroring_current_directory_tree
     initial_ignore = File.open(IGNORE_FILE, &:read)
 
@@ -106,4 +115,42 @@ class TestIgnore < TestWithBasicSetup
     exec_with_stdin(@@command_clear)
     assert_true File.zero?(IGNORE_FILE)
   end
+
+  def test_config_add_and_remove_should_preserve_comments_and_blank_lines
+    File.write(IGNORE_FILE, "# Scripts\n./blackbox_test.sh\n\n# Tests\n")
+
+    exec("#{@@command_add} test")
+    assert_equal "# Scripts\n./blackbox_test.sh\n\n# Tests\n./test\n", File.open(IGNORE_FILE, &:read)
+
+    exec("#{@@command_remove} blackbox_test.sh")
+    assert_equal "# Scripts\n\n# Tests\n./test\n", File.open(IGNORE_FILE, &:read)
+  end
+
+  def test_config_check_should_display_usage
+    exit_status, is_usage = output_usage?(@@command_check)
+    assert_not_equal 0, exit_status
+    assert_true      is_usage
+  end
+
+  def test_config_check_should_explain_verdict_for_each_file
+    FileUtils.touch(['file1', 'file2'])
+    File.write(IGNORE_FILE, "./file1\n")
+
+    output = exec("#{@@command_check} file1 file2 non_existing_file")
+    assert_equal 0, $?
+    assert_true output.include?('"./file1" is kept by "./file1" in ".kic/ignore" line 1')
+    assert_true output.include?('"./file2" is protected by "sweep.moratorium"')
+    assert_true output.include?('"./non_existing_file" does not exist')
+  end
+
+  def test_config_check_should_tell_dusts_skipped_for_size
+    File.write('file1', 'x' * 2048)
+    FileUtils.touch('file2')
+    exec('config set sweep.moratorium 0minute')
+    exec('config set sweep.max_size 1KiB')
+
+    output = exec("#{@@command_check} file1 file2")
+    assert_true output.include?('"./file1" is dust, but skipped for being larger than "sweep.max_size"')
+    assert_true output.include?('"./file2" is dust')
+  end
 end
@@ -0,0 +1,95 @@
+require_relative 'helper'
+
+class TestRestore < TestWithBasicSetup
+  @@command_restore = 'restore'
+
+  def setup
+    super
+
+    @dir1  = File.join('.', 'dir1')
+    @file1 = File.join(@dir1, 'file1')
+    FileUtils.mkdir_p(@dir1)
+    File.write(@file1, 'first')
+    exec('sweep all indeed')
+    FileUtils.mkdir_p(@dir1)
+    File.write(@file1, 'second')
+    exec('sweep all indeed')
+    assert_false File.exist?(@file1)
+  end
+
+  def teardown
+    super
+
+    FileUtils.remove_entry(@dir1) if File.exist?(@dir1)
+    FileUtils.remove_entry('out') if File.exist?('out')
+  end
+
+  def test_restore_should_display_usage
+    [@@command_restore, "#{@@command_restore} #{@file1} --box", "#{@@command_restore} #{@file1} --box ../.."].each do |command|
+      exit_status, is_usage = output_usage?(command)
+      assert_not_equal 0, exit_status
+      assert_true      is_usage
+    end
+  end
+
+  def test_restore_should_move_latest_version_back
+    exec("#{@@command_restore} 'dir1/*'")
+    assert_equal 'second', File.read(@file1)
+
+    result = exec("#{@@command_restore} #{@file1}")
+    assert_true  result.include?('already exists')
+    assert_equal 'second', File.read(@file1)
+
+    exec("#{@@command_restore} #{@file1} --to out")
+    assert_equal 'first', File.read(File.join('out', @file1))
+  end
+
+  def test_restore_should_forget_restored_file_in_box
+    exec("#{@@command_restore} #{@file1}")
+    manifest = File.read(File.join(BOX, 'manifest.toml'))
+    assert_true  manifest.include?('"dusts/dir1/file1"')
+    assert_false manifest.include?('"dusts/dir1/file1~1"')
+
+    result = exec('undo')
+    assert_false result.include?('has already gone from the box')
+    assert_equal 'second', File.read(@file1)
+  end
+
+  def test_restore_with_ignore_should_add_restored_files_to_ignore
+    exec("#{@@command_restore} #{@file1} --ignore")
+    assert_true File.exist?(@file1)
+    assert_true File.read(IGNORE_FILE).include?(@file1)
+  end
+
+  def test_restore_with_ignore_should_refuse_destination_out_of_current_directory
+    ['/tmp', '..'].each do |dir|
+      result = exec("#{@@command_restore} #{@file1} --to #{dir} --ignore")
+      assert_not_equal 0, $?
+      assert_true      result.include?('cannot be used with')
+    end
+    assert_false File.read(IGNORE_FILE).include?('/tmp')
+
+    exec("#{@@command_restore} #{@file1} --to out --ignore")
+    assert_true File.read(IGNORE_FILE).include?(File.join('.', 'out', @file1))
+  end
+
+  def test_restore_should_put_back_mode_and_mtime
+    file2 = File.join(@dir1, 'file2')
+    mtime = Time.local(2016, 5, 16, 1, 2, 3)
+    FileUtils.mkdir_p(@dir1)
+    File.write(file2, 'third')
+    File.chmod(0o4751, file2)
+    File.utime(mtime, mtime, file2)
+    exec('sweep all indeed')
+
+    exec("#{@@command_restore} #{file2}")
+    assert_equal 0o4751, File.stat(file2).mode & 0o7777
+    assert_equal mtime,  File.mtime(file2)
+  end
+
+  def test_restore_should_notice_non_existing_box
+    result = exec("#{@@command_restore} #{@file1} --box 2000-01-01")
+    assert_true  result.include?('does not exist')
+    assert_false File.exist?(@file1)
+  end
+end
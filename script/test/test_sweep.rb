@@ -135,4 +135,199 @@ class TestSweep < TestWithBasicSetup
       assert_true  File.exist?(not_dust)
     end
   end
+
+  def test_sweep_all_should_ignore_files_which_match_patterns
+    not_dusts = [@f2, @f3]
+    dusts     = [@f1]
+
+    exec("ignore add '#{@dir1}/**/file?'")
+
+    result = exec(@@command_sweep_all)
+    not_dusts.each do |not_dust|
+      assert_false result.include?(enclose(not_dust))
+    end
+    dusts.each do |dust|
+      assert_true result.include?(enclose(dust))
+    end
+  end
+
+  def test_sweep_all_should_move_files_which_match_negated_patterns_in_ignored_directory
+    not_dusts = [@f2]
+    dusts     = [@f3]
+
+    exec("ignore add #{@d1} '!#{@d2}/*'")
+
+    result = exec(@@command_sweep_all)
+    not_dusts.each do |not_dust|
+      assert_false result.include?(enclose(not_dust))
+    end
+    dusts.each do |dust|
+      assert_true result.include?(enclose(dust))
+    end
+  end
+
+  def test_sweep_all_should_move_hidden_files_if_they_are_included
+    dusts     = [@f4, @f5, @d4]
+    not_dusts = [File.join('.', '.kic')]
+
+    exec('config set sweep.include_hidden true')
+
+    result = exec(@@command_sweep_all)
+    dusts.each do |dust|
+      assert_true result.include?(enclose(dust))
+    end
+    not_dusts.each do |not_dust|
+      assert_false result.include?(enclose(not_dust))
+    end
+  end
+
+  def test_sweep_all_should_not_walk_into_nested_root
+    nested_root = File.join(@d1, '.kic')
+    FileUtils.mkdir_p(nested_root)
+
+    result = exec(@@command_sweep_all)
+    [@f2, @f3, @d3].each do |not_dust|
+      assert_false result.include?(enclose(not_dust))
+    end
+    assert_true result.include?(enclose(@f1))
+  end
+
+  def test_ignore_check_should_agree_with_sweep_at_nested_root
+    nested_root = File.join(@d1, '.kic')
+    FileUtils.mkdir_p(nested_root)
+
+    [@f2, @d2].each do |beyond|
+      result = exec("ignore check #{beyond}")
+      assert_true  result.include?("#{enclose(beyond)} is never swept because it is beyond the boundary")
+      assert_false result.include?("is dust")
+    end
+    assert_true exec("ignore check #{@f1}").include?(enclose(@f1))
+  end
+
+  def test_sweep_all_indeed_should_move_unreadable_dusts_and_the_rest
+    omit_if(Process.uid == 0, 'root can read any file')
+    File.chmod(0o000, @f2)
+
+    exec(@@command_sweep_all_indeed)
+    assert_equal 0, $?
+    [@f1, @f2, @f3].each do |dust|
+      assert_true  File.exist?(File.join(DUST_BOX, dust))
+      assert_false File.exist?(dust)
+    end
+  end
+
+  def test_sweep_should_keep_broken_link_without_hanging
+    broken = File.join(@d1, 'broken')
+    File.symlink('not_exist', broken)
+
+    result = exec(@@command_sweep)
+    assert_true result.include?("#{enclose(broken)} is kept because its metadata cannot be read")
+    assert_true File.symlink?(broken)
+  end
+
+  def test_sweep_all_should_keep_empty_dirs_by_policy
+    marker = File.join(@d2, 'KEEP')
+    FileUtils.touch(marker)
+    exec('config set sweep.keep_marker KEEP')
+    exec("ignore add #{@d3}")
+
+    result = exec(@@command_sweep_all)
+    [@d2, @d3, marker].each do |not_dust|
+      assert_false result.include?(enclose(not_dust))
+    end
+
+    exec_with_stdin('ignore clear')
+    exec('config set sweep.empty_dirs false')
+    result = exec(@@command_sweep_all)
+    assert_false result.include?(enclose(@d3))
+  end
+
+  def test_sweep_all_should_move_files_created_later_in_empty_dir_at_ignore_current
+    exec_with_stdin('ignore current')
+    later = File.join(@d3, 'later')
+    FileUtils.touch(later)
+
+    result = exec(@@command_sweep_all)
+    assert_true result.include?(enclose(later))
+  end
+
+  def test_sweep_all_indeed_should_agree_with_dry_run_on_ages_of_empty_dirs
+    exec('config set sweep.empty_dirs_min_age 1day')
+    old = Time.now - 3 * 24 * 60 * 60
+    [@f2, @f3, @d3, @d2, @d1].each do |path|
+      File.utime(old, old, path)
+    end
+
+    [@@command_sweep_all, @@command_sweep_all_indeed].each do |command|
+      result = exec(command)
+      [@d1, @d2, @d3].each do |dir|
+        assert_true result.include?(enclose(dir)), "#{command}: #{dir}"
+      end
+    end
+    assert_false File.exist?(@d1)
+  end
+
+  def test_sweep_all_indeed_should_put_dusts_in_buckets_beside_dusts
+    exec("config set sweep.buckets \"#{@dir1}: #{@file1}\"")
+    FileUtils.mv(@f1, "#{@f1}.#{@file1}")
+    FileUtils.mv(@f2, "#{@f2}.#{@file1}")
+
+    exec(@@command_sweep_all_indeed)
+    assert_true  File.exist?(File.join(BOX, 'buckets', @dir1, "#{@file1}.#{@file1}"))
+    assert_true  File.exist?(File.join(BOX, 'buckets', @dir1, @dir1, "#{@file2}.#{@file1}"))
+    assert_true  File.exist?(File.join(DUST_BOX, @d3))
+    assert_false File.exist?(File.join(DUST_BOX, @dir1, "#{@file1}.#{@file1}"))
+  end
+
+  def test_sweep_all_with_dirs_should_sweep_only_in_them
+    result = exec("#{@@command_sweep_all} #{@dir1}/#{@dir2}")
+    [@f3, @d3].each do |dust|
+      assert_true result.include?(enclose(dust))
+    end
+    [@f1, @f2, @d2].each do |not_dust|
+      assert_false result.include?(enclose(not_dust))
+    end
+  end
+
+  def test_sweep_all_with_dirs_should_read_only_local_ignore_files_affecting_them
+    ancestor, sibling = [File.join(@d1, '.kicignore'), File.join(@d5, '.kicignore')]
+    File.write(ancestor, File.join(@dir2, @file3))
+    File.write(sibling, @file5)
+
+    result = exec("#{@@command_sweep_all} #{@dir1}/#{@dir2}")
+    assert_true  result.include?(enclose(ancestor))
+    assert_false result.include?(enclose(sibling))
+    assert_false result.include?(enclose(@f3))
+  end
+
+  def test_sweep_all_with_invalid_dirs_should_fail
+    ['non_existing_dir', '..', @f1].each do |dir|
+      result = exec("#{@@command_sweep_all} #{dir}")
+      assert_not_equal 0, $?
+      assert_true      result.include?('is not a directory in current directory')
+    end
+  end
+
+  def test_sweep_interactive_should_add_kept_files_to_ignore_and_stop_at_quit
+    exec_with_stdin('sweep all --interactive', 'k')
+    assert_true File.read(IGNORE_FILE).include?(@f3)
+    [@f1, @f2, @f3].each do |not_dust|
+      assert_true  File.exist?(not_dust)
+      assert_false File.exist?(File.join(DUST_BOX, not_dust))
+    end
+
+    exec_with_stdin('sweep all --interactive', 'd')
+    assert_true  File.exist?(File.join(DUST_BOX, @f2))
+    assert_false File.exist?(@f2)
+    assert_true  File.exist?(@f1)
+  end
+
+  def test_sweep_interactive_should_ask_again_at_empty_answer_and_leave_empty_dirs
+    result = `printf '\\nd\\n' | #{BIN} sweep all --interactive`
+    assert_true  result.include?('Please answer')
+    assert_true  result.include?('Empty directories are not swept in interactive mode')
+    assert_true  File.exist?(File.join(DUST_BOX, @f3))
+    assert_false File.exist?(@f3)
+    assert_true  File.exist?(@d3)
+  end
 end
@@ -0,0 +1,50 @@
+require_relative 'helper'
+
+class TestUndo < TestWithBasicSetup
+  @@command_undo = 'undo'
+
+  def setup
+    super
+
+    @dir1  = File.join('.', 'dir1')
+    @dir2  = File.join(@dir1, 'dir2')
+    @file1 = File.join('.', 'file1')
+    @file2 = File.join(@dir1, 'file2')
+    FileUtils.mkdir_p(@dir2)
+    FileUtils.touch(@file1)
+    exec('sweep all indeed')
+    sleep 1
+    FileUtils.touch(@file2)
+    exec('sweep all indeed')
+  end
+
+  def teardown
+    super
+
+    FileUtils.rm(@file1)          if File.exist?(@file1)
+    FileUtils.remove_entry(@dir1) if File.exist?(@dir1)
+  end
+
+  def test_undo_should_move_back_only_latest_run
+    exec(@@command_undo)
+    assert_true  File.exist?(@file2)
+    assert_false File.exist?(@file1)
+    assert_false File.exist?(@dir2)
+
+    exec(@@command_undo)
+    assert_true File.exist?(@file1)
+    assert_true File.exist?(@dir2)
+
+    result = exec(@@command_undo)
+    assert_true result.include?('There is no run')
+  end
+
+  def test_undo_should_not_overwrite_existing_files
+    FileUtils.mkdir_p(@dir1)
+    File.write(@file2, 'new')
+
+    result = exec(@@command_undo)
+    assert_true  result.include?('already exists')
+    assert_equal 'new', File.read(@file2)
+  end
+end
@@ -1,8 +1,15 @@
 use error::{CliError, Usage, UsageKind};
 use super::Command;
 
-use constant::IGNORE_FILE_NAME;
+use constant::{IGNORE_FILE_NAME, MAIN_DIR};
+use lib::fs::*;
+use lib::io::*;
+use lib::judge::Judge;
+use lib::rule::SizeLimits;
 use lib::setting;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
 
 #[derive(Debug)]
 pub struct Ignore {
@@ -23,6 +30,7 @@ impl Command for Ignore {
                 "refresh" => Self::refresh(),
                 "current" => Self::ignore_current_files(),
                 "clear"   => Self::clear_ignore_file(),
+                "check"   => self.check(),
                 _         => Err(From::from(self.usage())),
             },
             None => Err(From::from(self.usage())),
@@ -60,15 +68,73 @@ impl Ignore {
     }
 
     fn refresh() -> Result<(), CliError> {
-        setting::Ignore::read()
-            .and_then(|ignore| ignore.create())
-            .map_err(|e| From::from(e))
+        let ignore = try!(setting::Ignore::read()).refresh();
+
+        ignore.create().map_err(|e| From::from(e))
+    }
+
+    fn check(&self) -> Result<(), CliError> {
+        let paths = &self.paths;
+
+        if paths.len() == 0 {
+            return Err(From::from(self.usage()));
+        }
+
+        let paths = paths
+            .iter()
+            .map(|p| trim_end_separator(supply_current_dir_prefix(p)))
+            .collect::<Vec<String>>();
+
+        let config   = try!(setting::Config::read());
+        let judge    = try!(Judge::read(&config, false, &paths));
+        let boundary = try!(Boundary::read(&config, MAIN_DIR));
+        let limits   = try!(SizeLimits::read(&config));
+
+        for path in paths {
+            if fs::symlink_metadata(&path).is_err() {
+                print_with_tag(Tag::Notice, format!("\"{}\" does not exist", path));
+                continue;
+            }
+            if judge.hidden().is_skipped_path(&path) {
+                print_with_tag(Tag::Notice, format!("\"{}\" is skipped as a hidden entry", path));
+                continue;
+            }
+            if boundary.is_beyond(&path) {
+                print_with_tag(Tag::Notice, format!("\"{}\" is never swept because it is beyond the boundary", path));
+                continue;
+            }
+
+            // "sweep" moves files in directories one by one, so check each of them as well.
+            let files = if Path::new(&path).is_dir() {
+                let fence = boundary.clone();
+                // No directory is abandoned, since "Some" never panics.
+                walk_files_in_parallel(&path, judge.hidden().clone(), move |d| fence.is_crossed(d), Some)
+                    .iter()
+                    .filter_map(Result::ok)
+                    .collect::<BTreeSet<String>>()
+            } else {
+                let mut files = BTreeSet::new();
+                files.insert(path);
+                files
+            };
+            for f in files {
+                let verdict = judge.judge(&f);
+                match limits.limit_of(&f) {
+                    Some(ref limit) if verdict.is_dust() => print_with_tag(Tag::Info, format!("\"{}\" is {}, but skipped for being {}", f, verdict, limit)),
+                    _                                    => print_with_tag(Tag::Info, format!("\"{}\" is {}", f, verdict)),
+                };
+            }
+        }
+
+        Ok(())
     }
 
     fn ignore_current_files() -> Result<(), CliError> {
         let message = "Do you want to preserve current state?";
 
-        Self::run_after_confirmation(message, || setting::Ignore::default().create().map_err(|e| From::from(e)))
+        let hidden = try!(HiddenPolicy::read(&try!(setting::Config::read())));
+
+        Self::run_after_confirmation(message, || setting::Ignore::default(&hidden).create().map_err(|e| From::from(e)))
     }
 
     fn clear_ignore_file() -> Result<(), CliError> {
@@ -1,6 +1,7 @@
 use error::{CliError, Usage, UsageKind};
 use super::Command;
 
+use lib::fs::HiddenPolicy;
 use lib::setting::{self, Config, Ignore, Storage};
 
 #[derive(Debug)]
@@ -23,7 +24,8 @@ impl Command for Init {
         }
 
         if !Ignore::exist() {
-            try!(Ignore::default().create());
+            let hidden = try!(HiddenPolicy::read(&try!(Config::read())));
+            try!(Ignore::default(&hidden).create());
         }
 
         Ok(())
@@ -4,6 +4,8 @@ mod config;
 mod ignore;
 mod sweep;
 mod burn;
+mod restore;
+mod undo;
 mod start;
 mod end;
 mod destroy;
@@ -15,6 +17,8 @@ use self::config::Config;
 use self::ignore::Ignore;
 use self::sweep::Sweep;
 use self::burn::Burn;
+use self::restore::Restore;
+use self::undo::Undo;
 use self::start::Start;
 use self::end::End;
 use self::destroy::Destroy;
@@ -107,8 +111,10 @@ pub fn execute(args: Vec<String>) -> Result<(), CliError> {
         "init"    => Init                                              .exec(need_help),
         "config"  => Config::new(args.next(), args.next(), args.next()).exec(need_help),
         "ignore"  => Ignore::new(args.next(), args.collect())          .exec(need_help),
-        "sweep"   => Sweep::new(args.next(), args.next())              .exec(need_help),
+        "sweep"   => Sweep::new(args.collect())                        .exec(need_help),
         "burn"    => Burn::new(args.next())                            .exec(need_help),
+        "restore" => Restore::new(args.collect())                      .exec(need_help),
+        "undo"    => Undo::new(args.next())                            .exec(need_help),
         "start"   => Start                                             .exec(need_help),
         "end"     => End                                               .exec(need_help),
         "destroy" => Destroy                                           .exec(need_help),
@@ -0,0 +1,135 @@
+use error::{CliError, Usage, UsageKind};
+use super::Command;
+
+use lib::fs::*;
+use lib::io::*;
+use lib::setting::{self, IgnoreRules, Storage};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug)]
+pub struct Restore {
+    args: Vec<String>,
+}
+
+impl Command for Restore {
+    fn usage(&self) -> Usage {
+        return Usage::new(UsageKind::Restore);
+    }
+
+    fn main(&self) -> Result<(), CliError> {
+        let (paths, date, to, ignore) = try!(self.parse_args());
+
+        if paths.len() == 0 {
+            return Err(From::from(self.usage()));
+        }
+        // "ignore" has only paths in current directory.
+        if ignore && !to.as_ref().map_or(true, |d| Self::is_in_current_dir(d)) {
+            print_with_tag(Tag::Notice, "\"--ignore\" cannot be used with \"--to\" out of current directory");
+            return Err(From::from(self.usage()));
+        }
+        if let Some(ref d) = date {
+            if !Storage::box_exists(d) {
+                print_with_tag(Tag::Notice, format!("\"{}\" box does not exist in \"warehouse\"", d));
+                return Ok(());
+            }
+        }
+
+        let targets = try!(IgnoreRules::from_arguments(&paths));
+
+        let mut found    = BTreeSet::new();
+        let mut restored = Vec::new();
+        for dust in try!(Storage::swept_dusts(date.as_ref())) {
+            // Only the latest version of each file is restored.
+            if !targets.is_ignored(&dust.origin) || !found.insert(dust.origin.clone()) {
+                continue;
+            }
+
+            let destination = match to {
+                Some(ref dir) => Path::new(dir).join(&dust.origin).components().collect::<PathBuf>(),
+                None          => PathBuf::from(&dust.origin),
+            };
+
+            if fs::symlink_metadata(&destination).is_ok() {
+                print_with_tag(Tag::Notice, format!("\"{}\" already exists, so it is not overwritten (use \"--to\" option)", destination.display()));
+                continue;
+            }
+
+            print_with_tag(Tag::Info, format!("Restore \"{}\" from \"{}\" box", destination.display(), dust.date));
+
+            if let Some(parent) = destination.parent() {
+                try!(fs::create_dir_all(parent));
+            }
+            match fs::rename(&dust.path, &destination) {
+                Err(ref e) if is_cross_device(e) => try!(copy_and_remove(&dust.path, &destination)),
+                result                           => try!(result),
+            };
+
+            if let Some(ref attributes) = dust.attributes {
+                let lost = try!(attributes.put_back(&destination));
+                if !lost.is_empty() {
+                    print_with_tag(Tag::Notice, format!("Cannot put back {} of \"{}\"", lost.join(", "), destination.display()));
+                }
+            }
+
+            try!(Storage::forget_dust(&dust));
+
+            restored.push(supply_current_dir_prefix(destination.to_string_lossy()));
+        }
+
+        if found.is_empty() {
+            print_with_tag(Tag::Notice, "There is no swept file to be restored");
+        }
+
+        if ignore && !restored.is_empty() {
+            let ignore = try!(setting::Ignore::read()).add(&restored);
+            try!(ignore.create());
+        }
+
+        Ok(())
+    }
+}
+
+impl Restore {
+    pub fn new(args: Vec<String>) -> Self {
+        Restore { args: args }
+    }
+
+    // A box must be right under "warehouse", not to touch anything out of it.
+    fn is_box_name(name: &str) -> bool {
+        let mut components = Path::new(name).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(_)), None) => true,
+            _                                  => false,
+        }
+    }
+
+    fn is_in_current_dir(dir: &str) -> bool {
+        Path::new(dir)
+            .components()
+            .all(|c| match c {
+                Component::CurDir | Component::Normal(_) => true,
+                _                                        => false,
+            })
+    }
+
+    fn parse_args(&self) -> Result<(Vec<String>, Option<String>, Option<String>, bool), CliError> {
+        let mut paths  = Vec::new();
+        let mut date   = None;
+        let mut to     = None;
+        let mut ignore = false;
+
+        let mut args = self.args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_ref() {
+                "--box"    => date   = Some(try!(args.next().filter(|d| Self::is_box_name(d)).ok_or(self.usage())).clone()),
+                "--to"     => to     = Some(try!(args.next().ok_or(self.usage())).clone()),
+                "--ignore" => ignore = true,
+                _          => paths.push(arg.clone()),
+            }
+        }
+
+        Ok((paths, date, to, ignore))
+    }
+}
@@ -3,13 +3,17 @@ use super::Command;
 
 use constant::MAIN_DIR;
 use lib::fs::*;
-use lib::setting::{Config, ConfigKey, Ignore, Storage};
-use std::path::{Path, PathBuf};
+use lib::io::*;
+use lib::judge::{Judge, Verdict};
+use lib::rule::{Buckets, SizeLimits};
+use lib::setting::{self, Config, Storage};
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct Sweep {
-    option1: Option<String>,
-    option2: Option<String>,
+    args: Vec<String>,
 }
 
 impl Command for Sweep {
@@ -18,54 +22,210 @@ impl Command for Sweep {
     }
 
     fn main(&self) -> Result<(), CliError> {
-        let indeed = [&self.option1, &self.option2]
-            .iter()
-            .any(|option| match option {
-                &&Some(ref o) => o == "indeed",
-                &&None        => false,
-            });
-        let all = match &self.option1 {
-            &Some(ref o) => match o.as_ref() {
-                "all"    => true,
-                "indeed" => false,
-                _        => return Err(From::from(self.usage())),
-            },
-            &None => false,
-        };
+        let (all, indeed, interactive, args) = try!(self.parse_args());
+        // Each "dust" is confirmed in interactive mode, so that it is moved indeed.
+        let indeed = indeed || interactive;
+
+        let mut target_dirs = Vec::new();
+        for arg in args {
+            match Self::to_target_dir(&arg) {
+                Some(dir) => target_dirs.push(dir),
+                None      => {
+                    print_with_tag(Tag::Notice, format!("\"{}\" is not a directory in current directory", arg));
+                    return Err(From::from(self.usage()));
+                },
+            }
+        }
+        if target_dirs.is_empty() {
+            target_dirs.push(MAIN_DIR.to_string());
+        }
 
         let storage = Storage::new("sweep", indeed);
         try!(storage.create_box());
 
-        let config     = try!(Config::read());
-        let moratorium = try!(config.get(ConfigKey::SweepMoratorium));
-        let moratorium = Config::to_duration(moratorium);
+        let target_dirs = Self::outermost_dirs(target_dirs);
 
-        let ignore = try!(Ignore::read());
-        let (ignored_dirs, ignored_files) = ignore.dirs_and_files();
+        let config = try!(Config::read());
+        let judge    = Arc::new(try!(Judge::read(&config, all, &target_dirs)));
+        let limits   = try!(SizeLimits::read(&config));
+        let buckets  = try!(Buckets::read(&config));
+        let boundary = try!(Boundary::read(&config, MAIN_DIR));
 
-        let target_files = walk_dir(MAIN_DIR)
-            .difference(&ignored_files)
-            .filter(|f| if all { true } else { !is_recently_accessed(f, &moratorium) })
-            .filter(|f| !ignored_dirs.iter().any(|d| f.starts_with(d)))
-            .cloned()
+        let target_dirs = target_dirs
+            .into_iter()
+            .filter(|d| {
+                let is_skipped = d != MAIN_DIR && (judge.hidden().is_skipped_path(d) || boundary.is_beyond(d));
+                if is_skipped {
+                    print_with_tag(Tag::Notice, format!("\"{}\" is never swept because it is hidden or beyond the boundary", d));
+                }
+                !is_skipped
+            })
             .collect::<Vec<String>>();
-        try!(storage.squeeze_dusts(&target_files));
 
-        let phantom_files = if indeed {
-            Vec::new()
-        } else {
-            target_files
+        // Verdicts are reported as soon as they are streamed, and only the paths of "dust"s are collected.
+        let mut candidates = BTreeSet::new();
+        for dir in &target_dirs {
+            let (pruner, visitor, fence) = (judge.clone(), judge.clone(), boundary.clone());
+            let walked = walk_files_in_parallel(
+                dir,
+                judge.hidden().clone(),
+                move |d| fence.is_crossed(d) || pruner.is_prunable(d),
+                move |f| {
+                    let verdict = visitor.judge(&f);
+                    match verdict {
+                        Verdict::KeptForError(_) => Some((f, verdict)),
+                        _ if verdict.is_dust()   => Some((f, verdict)),
+                        _                        => None,
+                    }
+                },
+            );
+            for result in walked {
+                let (f, verdict) = match result {
+                    Ok(judged) => judged,
+                    Err(dir)   => {
+                        print_with_tag(Tag::Notice, format!("\"{}\" is not walked to the end because of an error", dir.display()));
+                        continue;
+                    },
+                };
+                match verdict {
+                    Verdict::Dust            => (),
+                    Verdict::KeptForError(_) => { print_with_tag(Tag::Notice, format!("\"{}\" is {}", f, verdict)); continue },
+                    _                        => print_with_tag(Tag::Info, format!("\"{}\" is {}", f, verdict)),
+                };
+                candidates.insert(f);
+            }
+        }
+        let (mut target_files, skipped_files) = limits.apply(candidates);
+        try!(storage.skip_dusts(&skipped_files));
+        if interactive {
+            target_files = try!(Self::select_interactively(target_files));
+        }
+
+        // Check as late as possible, since any process may open the files at any time.
+        let files_in_use = if target_files.is_empty() { BTreeSet::new() } else { files_in_use() };
+        let (deferred_files, target_files) = target_files
+            .into_iter()
+            .partition::<Vec<String>, _>(|f| is_in_use(f, &files_in_use));
+        try!(storage.defer_dusts(&deferred_files));
+
+        // Only what was answered is moved in interactive mode.
+        let sweeps_empty_dirs = judge.empty_dirs().is_enabled() && !interactive;
+
+        // Find empty directories before moving "dust"s, which updates the timestamps of their directories,
+        // so that "sweep" and "sweep indeed" judge the ages of the directories in the same way.
+        let mut empty_dirs = BTreeSet::new();
+        if sweeps_empty_dirs {
+            let phantom_files = target_files
                 .iter()
-                .map(|f| Path::new(f).to_path_buf())
-                .collect::<Vec<PathBuf>>()
-        };
-        let potentially_empty_dirs = potentially_empty_dirs(MAIN_DIR, phantom_files);
-        storage.squeeze_empty_dirs(potentially_empty_dirs).map_err(|e| From::from(e))
+                .map(PathBuf::from)
+                .collect::<BTreeSet<PathBuf>>();
+            // The target directories themselves are never moved even if they are empty.
+            for dir in &target_dirs {
+                let mut dirs = potentially_empty_dirs(dir, &phantom_files, judge.hidden(), &boundary, |d| judge.is_kept_dir(d));
+                dirs.remove(Path::new(dir));
+                empty_dirs.extend(dirs);
+            }
+        }
+
+        try!(storage.squeeze_dusts(&target_files, &buckets));
+
+        if !sweeps_empty_dirs {
+            if interactive && judge.empty_dirs().is_enabled() {
+                print_with_tag(Tag::Notice, "Empty directories are not swept in interactive mode");
+            }
+            return Ok(());
+        }
+        storage.squeeze_empty_dirs(empty_dirs).map_err(|e| From::from(e))
     }
 }
 
 impl Sweep {
-    pub fn new(option1: Option<String>, option2: Option<String>) -> Self {
-        Sweep { option1: option1, option2: option2 }
+    pub fn new(args: Vec<String>) -> Self {
+        Sweep { args: args }
+    }
+
+    // Arguments other than options are directories to be swept.
+    fn parse_args(&self) -> Result<(bool, bool, bool, Vec<String>), CliError> {
+        let (mut all, mut indeed, mut interactive) = (false, false, false);
+        let mut dirs = Vec::new();
+
+        for (i, arg) in self.args.iter().enumerate() {
+            match arg.as_ref() {
+                "all" if i == 0 => all         = true,
+                "all"           => return Err(From::from(self.usage())),
+                "indeed"        => indeed      = true,
+                "--interactive" => interactive = true,
+                _               => dirs.push(arg.clone()),
+            }
+        }
+
+        Ok((all, indeed, interactive, dirs))
+    }
+
+    // Return a directory like "./dir" if it is in current directory.
+    fn to_target_dir(arg: &str) -> Option<String> {
+        let mut dir = PathBuf::from(MAIN_DIR);
+        for component in Path::new(arg).components() {
+            match component {
+                Component::CurDir    => continue,
+                Component::Normal(c) => dir.push(c),
+                _                    => return None,
+            }
+        }
+
+        if dir.is_dir() { dir.to_str().map(|d| d.to_string()) } else { None }
+    }
+
+    // Directories in another target directory are dropped, not to be swept twice.
+    fn outermost_dirs(mut dirs: Vec<String>) -> Vec<String> {
+        dirs.sort();
+        dirs.dedup();
+
+        let mut outermost: Vec<String> = Vec::new();
+        for dir in dirs {
+            if !outermost.iter().any(|o| Path::new(&dir).starts_with(o)) {
+                outermost.push(dir);
+            }
+        }
+        outermost
+    }
+
+    // Ask whether each candidate is a "dust", and register the files to be kept in "ignore".
+    fn select_interactively(candidates: Vec<String>) -> Result<Vec<String>, CliError> {
+        let mut dusts        = Vec::new();
+        let mut keeps        = Vec::new();
+        let mut skipped_dirs = Vec::new();
+
+        'candidates: for candidate in candidates {
+            let dir = Path::new(&candidate).parent().map_or(PathBuf::new(), |p| p.to_path_buf());
+            if skipped_dirs.iter().any(|d| dir.starts_with(d)) {
+                continue;
+            }
+
+            loop {
+                let question = format!("Is \"{}\" a dust? [k]eep, [d]ust, [s]kip the directory or [q]uit: ", candidate);
+                echo(format_with_tag(Tag::Caution, question));
+
+                // An empty line is asked again, not to drop the rest by pressing Enter by mistake.
+                let answer = match try!(read_line_or_eof_from_stdin()) {
+                    Some(a) => a.to_lowercase(),
+                    None    => { print_with_tag(Tag::Notice, "Interrupted at the end of input"); break 'candidates },
+                };
+                match answer.as_ref() {
+                    "k" | "keep" => { keeps.push(candidate); break },
+                    "d" | "dust" => { dusts.push(candidate); break },
+                    "s" | "skip" => { skipped_dirs.push(dir); break },
+                    "q" | "quit" => { print_with_tag(Tag::Notice, "Interrupted by user"); break 'candidates },
+                    _            => print_with_tag(Tag::Notice, "Please answer \"k\", \"d\", \"s\" or \"q\""),
+                };
+            }
+        }
+
+        if !keeps.is_empty() {
+            let ignore = try!(setting::Ignore::read()).add(&keeps);
+            try!(ignore.create());
+        }
+
+        Ok(dusts)
     }
 }
@@ -0,0 +1,25 @@
+use error::{CliError, Usage, UsageKind};
+use super::Command;
+
+use lib::setting::Storage;
+
+#[derive(Debug)]
+pub struct Undo {
+    run: Option<String>,
+}
+
+impl Command for Undo {
+    fn usage(&self) -> Usage {
+        return Usage::new(UsageKind::Undo);
+    }
+
+    fn main(&self) -> Result<(), CliError> {
+        Storage::undo(self.run.as_ref())
+    }
+}
+
+impl Undo {
+    pub fn new(run: Option<String>) -> Self {
+        Undo { run: run }
+    }
+}
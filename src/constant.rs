@@ -54,3 +54,5 @@ pub const WORKING_DIR_NAME: &'static str = ".kic";
 pub const STORAGE_DIR_NAME: &'static str = "warehouse";
 pub const CONFIG_FILE_NAME: &'static str = "config.toml";
 pub const IGNORE_FILE_NAME: &'static str = "ignore";
+
+pub const LOCAL_IGNORE_FILE_NAME: &'static str = ".kicignore";
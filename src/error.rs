@@ -16,6 +16,7 @@ pub enum CliError {
     Config(ConfigError),
     Cron(CronError),
     Essential(EssentialLack),
+    Ignore(IgnoreError),
     Io(IoError),
     ParseInt(ParseIntError),
     ParseToml(ParseTomlError),
@@ -29,6 +30,7 @@ impl Display for CliError {
             CliError::Config(ref e)       => e.fmt(f),
             CliError::Cron(ref e)         => e.fmt(f),
             CliError::Essential(ref e)    => e.fmt(f),
+            CliError::Ignore(ref e)       => e.fmt(f),
             CliError::Io(ref e)           => e.fmt(f),
             CliError::ParseInt(ref e)     => e.fmt(f),
             CliError::ParseToml(ref e)    => e.fmt(f),
@@ -44,6 +46,7 @@ impl Error for CliError {
             CliError::Config(ref e)       => Some(e),
             CliError::Cron(ref e)         => Some(e),
             CliError::Essential(ref e)    => Some(e),
+            CliError::Ignore(ref e)       => Some(e),
             CliError::Io(ref e)           => Some(e),
             CliError::ParseInt(ref e)     => Some(e),
             CliError::ParseToml(ref e)    => Some(e),
@@ -58,6 +61,7 @@ impl Error for CliError {
             CliError::Config(ref e)       => e.description(),
             CliError::Cron(ref e)         => e.description(),
             CliError::Essential(ref e)    => e.description(),
+            CliError::Ignore(ref e)       => e.description(),
             CliError::Io(ref e)           => e.description(),
             CliError::ParseInt(ref e)     => e.description(),
             CliError::ParseToml(ref e)    => e.description(),
@@ -82,6 +86,11 @@ impl From<EssentialLack> for CliError {
         CliError::Essential(e)
     }
 }
+impl From<IgnoreError> for CliError {
+    fn from(e: IgnoreError) -> CliError {
+        CliError::Ignore(e)
+    }
+}
 impl From<IoError> for CliError {
     fn from(e: IoError) -> CliError {
         CliError::Io(e)
@@ -127,6 +136,26 @@ pub enum ConfigErrorKind {
     SweepMoratorium,
     SweepPeriod,
     SweepTime,
+    SweepKeepGitTracked,
+    SweepTimestamp,
+    SweepIncludeHidden,
+    SweepHiddenExclusions,
+    SweepOneFileSystem,
+    SweepEmptyDirs,
+    SweepEmptyDirsMinAge,
+    SweepKeepMarker,
+    SweepMinSize,
+    SweepMaxSize,
+    SweepMaxTotalBytes,
+    SweepBuckets,
+    RulesKeepLargerThan,
+    RulesKeepExtensions,
+    RulesKeepOwners,
+    RulesKeepMode,
+    RulesSweepSmallerThan,
+    RulesSweepExtensions,
+    RulesSweepOwners,
+    RulesSweepMode,
 }
 #[derive(Debug, PartialEq)]
 pub struct ConfigError {
@@ -141,7 +170,7 @@ impl Display for ConfigError {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
         write!(f, "{}", match self.kind {
             ConfigErrorKind::Something               => r#"Something went to wrong"#,
-            ConfigErrorKind::InvalidKey              => r#"Please set key in ["burn.moratorium", "sweep.moratorium", "sweep.period", "sweep.time"]"#,
+            ConfigErrorKind::InvalidKey              => r#"Please set key in ["burn.moratorium", "sweep.moratorium", "sweep.period", "sweep.time", "sweep.keep_git_tracked", "sweep.timestamp", "sweep.include_hidden", "sweep.hidden_exclusions", "sweep.one_file_system", "sweep.empty_dirs", "sweep.empty_dirs_min_age", "sweep.keep_marker", "sweep.min_size", "sweep.max_size", "sweep.max_total_bytes", "sweep.buckets", "rules.keep_larger_than", "rules.keep_extensions", "rules.keep_owners", "rules.keep_mode", "rules.sweep_smaller_than", "rules.sweep_extensions", "rules.sweep_owners", "rules.sweep_mode"]"#,
             ConfigErrorKind::NonStringValue          => r#"Please set values as "String""#,
             ConfigErrorKind::NotFoundBurnMoratorium  => r#"Please set "burn.moratorium""#,
             ConfigErrorKind::NotFoundSweepMoratorium => r#"Please set "sweep.moratorium""#,
@@ -151,6 +180,26 @@ impl Display for ConfigError {
             ConfigErrorKind::SweepMoratorium         => r#"Please set value like "10hours" or "1week" as "sweep.moratorium""#,
             ConfigErrorKind::SweepPeriod             => r#"Please set "daily" or "weekly" as "sweep.period""#,
             ConfigErrorKind::SweepTime               => r#"Please set value from "00:00" to "23:59" as "sweep.time""#,
+            ConfigErrorKind::SweepKeepGitTracked     => r#"Please set "true" or "false" as "sweep.keep_git_tracked""#,
+            ConfigErrorKind::SweepTimestamp          => r#"Please set "atime", "mtime", "ctime" or "newest" as "sweep.timestamp""#,
+            ConfigErrorKind::SweepIncludeHidden      => r#"Please set "true" or "false" as "sweep.include_hidden""#,
+            ConfigErrorKind::SweepHiddenExclusions   => r#"Please set hidden names like ".git, .DS_Store" as "sweep.hidden_exclusions""#,
+            ConfigErrorKind::SweepOneFileSystem      => r#"Please set "true" or "false" as "sweep.one_file_system""#,
+            ConfigErrorKind::SweepEmptyDirs          => r#"Please set "true" or "false" as "sweep.empty_dirs""#,
+            ConfigErrorKind::SweepEmptyDirsMinAge    => r#"Please set value like "12hours" or "3days" as "sweep.empty_dirs_min_age""#,
+            ConfigErrorKind::SweepKeepMarker         => r#"Please set a file name like ".kickeep" as "sweep.keep_marker""#,
+            ConfigErrorKind::SweepMinSize            => r#"Please set value like "1 MiB" or "100KB" as "sweep.min_size""#,
+            ConfigErrorKind::SweepMaxSize            => r#"Please set value like "1 GiB" or "500MB" as "sweep.max_size""#,
+            ConfigErrorKind::SweepMaxTotalBytes      => r#"Please set value like "10 GiB" or "1TB" as "sweep.max_total_bytes""#,
+            ConfigErrorKind::SweepBuckets            => r#"Please set value like "images: png, image/*; archives: zip" as "sweep.buckets""#,
+            ConfigErrorKind::RulesKeepLargerThan     => r#"Please set value like "1 GiB" or "500MB" as "rules.keep_larger_than""#,
+            ConfigErrorKind::RulesKeepExtensions     => r#"Please set value like "iso, tar.gz" as "rules.keep_extensions""#,
+            ConfigErrorKind::RulesKeepOwners         => r#"Please set uids like "0, 1000" as "rules.keep_owners""#,
+            ConfigErrorKind::RulesKeepMode           => r#"Please set octal bits like "111" or "4000" as "rules.keep_mode""#,
+            ConfigErrorKind::RulesSweepSmallerThan   => r#"Please set value like "1 KiB" or "100B" as "rules.sweep_smaller_than""#,
+            ConfigErrorKind::RulesSweepExtensions    => r#"Please set value like "part, crdownload" as "rules.sweep_extensions""#,
+            ConfigErrorKind::RulesSweepOwners        => r#"Please set uids like "0, 1000" as "rules.sweep_owners""#,
+            ConfigErrorKind::RulesSweepMode          => r#"Please set octal bits like "002" as "rules.sweep_mode""#,
         })
     }
 }
@@ -221,6 +270,27 @@ impl Error for EssentialLack {
 }
 
 
+#[derive(Debug)]
+pub struct IgnoreError {
+    entry : String,
+    origin: String,
+    cause : RegexError,
+}
+impl IgnoreError {
+    pub fn new(entry: String, origin: String, cause: RegexError) -> IgnoreError {
+        IgnoreError { entry: entry, origin: origin, cause: cause }
+    }
+}
+impl Display for IgnoreError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "Invalid pattern \"{}\" in {}\n{}", self.entry, self.origin, self.cause)
+    }
+}
+impl Error for IgnoreError {
+    fn description(&self) -> &str { "invalid pattern in ignore file" }
+}
+
+
 #[derive(Debug, PartialEq)]
 pub struct RunningPlaceError {
     dir: String,
@@ -250,6 +320,8 @@ pub enum UsageKind {
     Ignore,
     Sweep,
     Burn,
+    Restore,
+    Undo,
     Start,
     End,
     Destroy,
@@ -266,6 +338,8 @@ impl UsageKind {
             UsageKind::Ignore  => "ignore",
             UsageKind::Sweep   => "sweep",
             UsageKind::Burn    => "burn",
+            UsageKind::Restore => "restore",
+            UsageKind::Undo    => "undo",
             UsageKind::Start   => "start",
             UsageKind::End     => "end",
             UsageKind::Destroy => "destroy",
@@ -285,14 +359,17 @@ impl UsageKind {
                 format!("{} init"             , self.common_usage()),
             ],
             UsageKind::Ignore => vec![
-                format!("{} add <File> ..."   , self.common_usage()),
-                format!("{} remove <File> ...", self.common_usage()),
-                format!("{} refresh"          , self.common_usage()),
-                format!("{} current"          , self.common_usage()),
-                format!("{} clear"            , self.common_usage()),
+                format!("{} add <File|Pattern> ..."   , self.common_usage()),
+                format!("{} remove <File|Pattern> ...", self.common_usage()),
+                format!("{} refresh"                  , self.common_usage()),
+                format!("{} current"                  , self.common_usage()),
+                format!("{} clear"                    , self.common_usage()),
+                format!("{} check <File> ..."         , self.common_usage()),
             ],
-            UsageKind::Sweep => vec![format!("{} [all] [indeed]", self.common_usage())],
+            UsageKind::Sweep => vec![format!("{} [all] [indeed|--interactive] [<Dir> ...]", self.common_usage())],
             UsageKind::Burn  => vec![format!("{} [indeed]", self.common_usage())],
+            UsageKind::Restore => vec![format!("{} <File|Pattern> ... [--box <Date>] [--to <Dir>] [--ignore]", self.common_usage())],
+            UsageKind::Undo    => vec![format!("{} [<Run>]", self.common_usage())],
             _ => vec![self.common_usage()],
         }
     }
@@ -307,6 +384,8 @@ impl UsageKind {
             UsageKind::Ignore  => "Change \"ignore\" file's contents",
             UsageKind::Sweep   => "Move dust files and empty directories into \"warehouse\" directory",
             UsageKind::Burn    => "Delete expired directories in \"warehouse\" directory",
+            UsageKind::Restore => "Move swept files in \"warehouse\" directory back to where they came from",
+            UsageKind::Undo    => "Move files and empty directories back which the latest \"sweep\" moved",
             UsageKind::Start   => "Start automatic \"sweep\" and \"burn\" (UNIX-like: cron, Windows: ?)",
             UsageKind::End     => "End automatic \"sweep\" and \"burn\" (UNIX-like: cron, Windows: ?)",
             UsageKind::Destroy => "Unregister current directory, i.e. delete \".kic\" directory",
@@ -324,6 +403,8 @@ impl UsageKind {
                 format!("{}{}", "ignore  # ", UsageKind::Ignore .description()),
                 format!("{}{}", "sweep   # ", UsageKind::Sweep  .description()),
                 format!("{}{}", "burn    # ", UsageKind::Burn   .description()),
+                format!("{}{}", "restore # ", UsageKind::Restore.description()),
+                format!("{}{}", "undo    # ", UsageKind::Undo   .description()),
                 format!("{}{}", "start   # ", UsageKind::Start  .description()),
                 format!("{}{}", "end     # ", UsageKind::End    .description()),
                 format!("{}{}", "destroy # ", UsageKind::Destroy.description()),
@@ -334,17 +415,20 @@ impl UsageKind {
                 "init # Initialize \"config.toml\" file"                          .to_string(),
             ],
             UsageKind::Ignore => vec![
-                "add     # Add directories and files which will be ignored to \"ignore\" file"        .to_string(),
+                "add     # Add directories, files and patterns (\"!\" prefix: not ignored, \"+\" prefix: pinned) to \"ignore\" file".to_string(),
+                "          Unlike \".gitignore\", patterns match only in current directory, e.g. \"**/*.md\" is needed for \"./docs/a.md\"".to_string(),
                 "remove  # Remove directories and files which have been ignored from \"ignore\" file" .to_string(),
-                "refresh # Remove non-existing directories and files from \"ignore\" file"            .to_string(),
+                "refresh # Remove non-existing directories and files except pinned ones from \"ignore\" file".to_string(),
                 "current # Replace \"ignore\" file with one which register current all files"         .to_string(),
                 "clear   # Clear \"ignore\" file, i.e. all files will be aimed from \"sweep\" command".to_string(),
+                "check   # Show whether files are \"dust\"s for \"sweep\" command and why"              .to_string(),
             ],
             UsageKind::Sweep => vec![
-                "(none)     # Move fakely dust files into \"warehouse\""                                  .to_string(),
-                "indeed     # Move indeed dust files into \"warehouse\""                                  .to_string(),
-                "all        # Move fakely dust files into \"warehouse\" including recently accessed files".to_string(),
-                "all indeed # Move indeed dust files into \"warehouse\" including recently accessed files".to_string(),
+                "(none)        # Move fakely dust files into \"warehouse\""                                  .to_string(),
+                "indeed        # Move indeed dust files into \"warehouse\""                                  .to_string(),
+                "all           # Move fakely dust files into \"warehouse\" including recently accessed files".to_string(),
+                "all indeed    # Move indeed dust files into \"warehouse\" including recently accessed files".to_string(),
+                "--interactive # Ask whether each dust file is moved indeed into \"warehouse\" or kept by \"ignore\"".to_string(),
             ],
             UsageKind::Burn => vec![
                 "(none) # Delete expired directories in \"warehouse\" fakely".to_string(),
@@ -357,10 +441,41 @@ impl UsageKind {
     fn optional_items(&self) -> (&str, Vec<&str>) {
         match *self {
             UsageKind::Config => ("Keys", vec![
-                r#"burn.moratorium  # Moratorium to delete directories in "warehouse""#,
-                r#"sweep.moratorium # Moratorium to Move "dust"s into "warehouse""#,
-                r#"sweep.period     # Period to Move "dust"s by automatic "sweep""#,
-                r#"sweep.time       # Time to Move "dust"s by automatic "sweep""#,
+                r#"burn.moratorium          # Moratorium to delete directories in "warehouse""#,
+                r#"sweep.moratorium         # Moratorium to Move "dust"s into "warehouse""#,
+                r#"sweep.period             # Period to Move "dust"s by automatic "sweep""#,
+                r#"sweep.time               # Time to Move "dust"s by automatic "sweep""#,
+                r#"sweep.keep_git_tracked   # Whether files tracked by "git" are never "dust"s (Optional)"#,
+                r#"sweep.timestamp          # Timestamp which "sweep.moratorium" is compared against (Optional)"#,
+                r#"sweep.include_hidden     # Whether hidden files and directories can be "dust"s (Optional)"#,
+                r#"sweep.hidden_exclusions  # Hidden names never to be "dust"s even if "sweep.include_hidden" is "true" (Optional)"#,
+                r#"sweep.one_file_system    # Whether "sweep" stays on the filesystem of the root (Optional)"#,
+                r#"sweep.empty_dirs         # Whether empty directories can be "dust"s (Optional)"#,
+                r#"sweep.empty_dirs_min_age # Empty directories modified within this period are not "dust"s (Optional)"#,
+                r#"sweep.keep_marker        # Name of files which keep directories having them and are never "dust"s (Optional)"#,
+                r#"sweep.min_size           # Files smaller than this size are skipped by "sweep" (Optional)"#,
+                r#"sweep.max_size           # Files larger than this size are skipped by "sweep" for review (Optional)"#,
+                r#"sweep.max_total_bytes    # Total size of files moved by a run of "sweep" (Optional)"#,
+                r#"sweep.buckets            # Buckets beside "dusts" which "dust"s are put in by extensions or MIME types (Optional)"#,
+                r#"rules.keep_larger_than   # Files larger than this size are never "dust"s (Optional)"#,
+                r#"rules.keep_extensions    # Files with these extensions are never "dust"s (Optional)"#,
+                r#"rules.keep_owners        # Files owned by these uids are never "dust"s (Optional)"#,
+                r#"rules.keep_mode          # Files with any of these permission bits are never "dust"s (Optional)"#,
+                r#"rules.sweep_smaller_than # Files smaller than this size are always "dust"s (Optional)"#,
+                r#"rules.sweep_extensions   # Files with these extensions are always "dust"s (Optional)"#,
+                r#"rules.sweep_owners       # Files owned by these uids are always "dust"s (Optional)"#,
+                r#"rules.sweep_mode         # Files with any of these permission bits are always "dust"s (Optional)"#,
+            ]),
+            UsageKind::Restore => ("Options", vec![
+                r#"--box <Date> # Restore from the box of the date like "2016-05-17" instead of the newest one which has the file"#,
+                r#"--to <Dir>   # Restore into "<Dir>" keeping the relative path instead of the original place"#,
+                r#"--ignore     # Add restored files to "ignore" not to be swept again"#,
+            ]),
+            UsageKind::Sweep => ("Arguments", vec![
+                r#"<Dir> # Sweep only in "<Dir>" under current directory while reading settings of current directory (default: all)"#,
+            ]),
+            UsageKind::Undo => ("Arguments", vec![
+                r#"<Run> # Run ID recorded in "manifest.toml" and "sweep.log" like "20160517130506-1234" (default: the latest one)"#,
             ]),
             _ => ("", Vec::new()),
         }
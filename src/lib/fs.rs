@@ -1,16 +1,25 @@
 extern crate chrono;
 extern crate walkdir;
 
-use self::chrono::{Duration, UTC};
+use self::chrono::{DateTime, Duration, UTC};
+use self::chrono::offset::TimeZone;
 use self::walkdir::{DirEntry as WalkDirEntry, WalkDir, WalkDirIterator};
 
+use constant::{LOCAL_IGNORE_FILE_NAME, MAIN_DIR, WORKING_DIR_NAME};
+use error::ConfigError;
+use lib::setting::{Config, ConfigKey};
 use std::borrow::Borrow;
 use std::collections::{BTreeSet, VecDeque};
-use std::fs::{self, DirEntry};
-use std::io::Error as IoError;
-use std::os::unix::fs::MetadataExt;
+use std::ffi::OsStr;
+use std::fs::{self, DirEntry, File, FileTimes, OpenOptions};
+use std::io::{Error as IoError, ErrorKind as IoErrorKind, Read};
+use std::os::unix::fs::{self as unix_fs, MetadataExt, PermissionsExt};
 use std::path::{Component, MAIN_SEPARATOR, Path, PathBuf};
 use std::result::Result;
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread;
+use std::time::{Duration as StdDuration, UNIX_EPOCH};
 
 
 macro_rules! path_buf {
@@ -30,8 +39,6 @@ trait DirEntryExt {
     fn file_name_string(&self) -> Option<String> {
         unimplemented!();
     }
-
-    fn is_hidden(&self) -> bool;
 }
 impl DirEntryExt for DirEntry {
     fn file_name_string(&self) -> Option<String> {
@@ -40,20 +47,124 @@ impl DirEntryExt for DirEntry {
             .into_string()
             .ok()
     }
+}
 
-    fn is_hidden(&self) -> bool {
-        self
-            .file_name()
-            .to_str()
-            .map_or(false, is_hidden_name)
+// Which hidden entries are skipped by "sweep".
+#[derive(Clone, Debug)]
+pub struct HiddenPolicy {
+    include   : bool,
+    exclusions: Vec<String>,
+}
+
+impl HiddenPolicy {
+    pub fn new(include: bool, exclusions: Vec<String>) -> Self {
+        HiddenPolicy { include: include, exclusions: exclusions }
+    }
+
+    pub fn read(config: &Config) -> Result<Self, ConfigError> {
+        let include    = Config::to_bool(try!(config.get(ConfigKey::SweepIncludeHidden)));
+        let exclusions = Config::to_list(try!(config.get(ConfigKey::SweepHiddenExclusions)));
+
+        Ok(Self::new(include, exclusions))
+    }
+
+    fn is_skipped_name(&self, file_name: &OsStr) -> bool {
+        let file_name = match file_name.to_str() {
+            Some(n) => n,
+            None    => return false,
+        };
+        // Settings of "kic" itself must never be swept whatever the configuration is.
+        if file_name == WORKING_DIR_NAME || file_name == LOCAL_IGNORE_FILE_NAME {
+            return true;
+        }
+
+        is_hidden_name(file_name) && (!self.include || self.exclusions.iter().any(|e| e == file_name))
+    }
+
+    pub fn is_skipped_path<P: AsRef<Path>>(&self, path: P) -> bool {
+        path
+            .as_ref()
+            .components()
+            .any(|c| match c {
+                Component::Normal(name) => self.is_skipped_name(name),
+                _                       => false,
+            })
     }
 }
-impl DirEntryExt for WalkDirEntry {
-    fn is_hidden(&self) -> bool {
-        self
-            .file_name()
-            .to_str()
-            .map_or(false, is_hidden_name)
+
+// How "sweep" treats empty directories.
+#[derive(Clone, Debug)]
+pub struct EmptyDirPolicy {
+    enabled: bool,
+    min_age: Duration,
+    marker : String,
+}
+
+impl EmptyDirPolicy {
+    pub fn new(enabled: bool, min_age: Duration, marker: String) -> Self {
+        EmptyDirPolicy { enabled: enabled, min_age: min_age, marker: marker }
+    }
+
+    pub fn read(config: &Config) -> Result<Self, ConfigError> {
+        let enabled = Config::to_bool(try!(config.get(ConfigKey::SweepEmptyDirs)));
+        let min_age = Config::to_duration(try!(config.get(ConfigKey::SweepEmptyDirsMinAge)));
+        let marker  = try!(config.get(ConfigKey::SweepKeepMarker));
+
+        Ok(Self::new(enabled, min_age, marker))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn is_marker<P: AsRef<Path>>(&self, path: P) -> bool {
+        path.as_ref().file_name().map_or(false, |n| n == self.marker.as_str())
+    }
+
+    // The age is measured by "mtime", which changes whenever an entry in the directory is added or removed.
+    pub fn is_too_young<P: AsRef<Path>>(&self, dir: P) -> bool {
+        match fs::symlink_metadata(dir) {
+            Ok(m)  => UTC.timestamp(m.mtime(), 0) + self.min_age > UTC::now(),
+            Err(_) => false,
+        }
+    }
+}
+
+
+// Where "sweep" stops walking: nested roots of "kic", and other filesystems if "sweep.one_file_system" is "true".
+#[derive(Clone, Debug)]
+pub struct Boundary {
+    device: Option<u64>,
+}
+
+impl Boundary {
+    pub fn new<P: AsRef<Path>>(root: P, one_file_system: bool) -> Self {
+        let device = if one_file_system { fs::metadata(root).ok().map(|m| m.dev()) } else { None };
+        Boundary { device: device }
+    }
+
+    pub fn read<P: AsRef<Path>>(config: &Config, root: P) -> Result<Self, ConfigError> {
+        let one_file_system = Config::to_bool(try!(config.get(ConfigKey::SweepOneFileSystem)));
+
+        Ok(Self::new(root, one_file_system))
+    }
+
+    fn is_nested_root<P: AsRef<Path>>(dir: P) -> bool {
+        dir.as_ref().join(WORKING_DIR_NAME).is_dir()
+    }
+
+    // Only directories under the root are expected, since the root itself is always walked.
+    pub fn is_crossed<P: AsRef<Path>>(&self, dir: P) -> bool {
+        let dir = dir.as_ref();
+        Self::is_nested_root(dir) || self.device.map_or(false, |d| fs::symlink_metadata(dir).map_or(true, |m| m.dev() != d))
+    }
+
+    // Whether "sweep" from current directory never reaches "path", i.e. the path or any directory on the way to it is crossed.
+    pub fn is_beyond<P: AsRef<Path>>(&self, path: P) -> bool {
+        path.as_ref()
+            .ancestors()
+            .take_while(|a| *a != Path::new(MAIN_DIR) && *a != Path::new(""))
+            .any(|a| a.is_dir() && self.is_crossed(a))
     }
 }
 
@@ -86,26 +197,339 @@ pub fn la<P: AsRef<Path>>(path: P) -> Result<Vec<String>, IoError> {
     Ok(dirs)
 }
 
-#[cfg(unix)]
-pub fn is_recently_accessed<P: AsRef<Path>, D: Borrow<Duration>>(p: P, moratorium: D) -> bool {
-    let threshold = UTC::now() - *moratorium.borrow();
+// Which timestamp the moratorium is compared against.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimestampBasis {
+    Atime,
+    Mtime,
+    Ctime,
+    Newest,
+}
 
-    let accessed_time = match p.as_ref().metadata() {
-        Ok(m)  => m.atime(),
-        Err(_) => unreachable!("Wrong to use this function!!"),
+impl TimestampBasis {
+    pub fn from<S: AsRef<str>>(value: S) -> Self {
+        match value.as_ref() {
+            "atime"  => TimestampBasis::Atime,
+            "mtime"  => TimestampBasis::Mtime,
+            "ctime"  => TimestampBasis::Ctime,
+            "newest" => TimestampBasis::Newest,
+            _        => unreachable!("Wrong to use this function!!"),
+        }
+    }
+}
+
+// Return when the moratorium expires if the file was recently used.
+// The file may be a broken symbolic link, or may be removed by others after it was found.
+#[cfg(unix)]
+pub fn protected_until<P: AsRef<Path>, D: Borrow<Duration>>(p: P, moratorium: D, basis: TimestampBasis) -> Result<Option<DateTime<UTC>>, IoError> {
+    let metadata = try!(p.as_ref().metadata());
+    let timestamp = match basis {
+        TimestampBasis::Atime  => metadata.atime(),
+        TimestampBasis::Mtime  => metadata.mtime(),
+        TimestampBasis::Ctime  => metadata.ctime(),
+        TimestampBasis::Newest => *[metadata.atime(), metadata.mtime(), metadata.ctime()].iter().max().unwrap(),
     };
 
-    accessed_time > threshold.timestamp()
+    let until = UTC.timestamp(timestamp, 0) + *moratorium.borrow();
+    Ok(if until > UTC::now() { Some(until) } else { None })
 }
 #[cfg(windows)]
-pub fn is_recently_accessed<P: AsRef<Path>, D: Borrow<Duration>>(_p: P, _moratorium: D) -> bool {
-    false
+pub fn protected_until<P: AsRef<Path>, D: Borrow<Duration>>(_p: P, _moratorium: D, _basis: TimestampBasis) -> Result<Option<DateTime<UTC>>, IoError> {
+    Ok(None)
+}
+
+// Return files opened by the processes which can be seen in "/proc".
+#[cfg(target_os = "linux")]
+pub fn files_in_use() -> BTreeSet<PathBuf> {
+    let processes = match fs::read_dir("/proc") {
+        Ok(p)  => p,
+        Err(_) => return BTreeSet::new(),
+    };
+
+    processes
+        .filter_map(Result::ok)
+        .filter(|e| e.file_name().to_str().map_or(false, |n| n.chars().all(|c| c.is_digit(10))))
+        .filter_map(|e| fs::read_dir(e.path().join("fd")).ok())
+        .flat_map(|fds| fds.filter_map(Result::ok).filter_map(|fd| fs::read_link(fd.path()).ok()))
+        .collect::<BTreeSet<PathBuf>>()
+}
+#[cfg(not(target_os = "linux"))]
+pub fn files_in_use() -> BTreeSet<PathBuf> {
+    BTreeSet::new()
+}
+
+// Only the parent is canonicalized, since a symbolic link itself is what is moved.
+pub fn is_in_use<P: AsRef<Path>>(path: P, files_in_use: &BTreeSet<PathBuf>) -> bool {
+    let path = path.as_ref();
+    match (path.parent().and_then(|p| fs::canonicalize(p).ok()), path.file_name()) {
+        (Some(parent), Some(name)) => files_in_use.contains(&parent.join(name)),
+        _                          => false,
+    }
+}
+
+const MAGIC_NUMBERS: [(&'static [u8], &'static str); 15] = [
+    (b"\x89PNG\r\n\x1a\n" , "image/png"                  ),
+    (b"\xff\xd8\xff"      , "image/jpeg"                 ),
+    (b"GIF87a"            , "image/gif"                  ),
+    (b"GIF89a"            , "image/gif"                  ),
+    (b"%PDF-"             , "application/pdf"            ),
+    (b"PK\x03\x04"        , "application/zip"            ),
+    (b"\x1f\x8b"          , "application/gzip"           ),
+    (b"BZh"               , "application/x-bzip2"        ),
+    (b"\xfd7zXZ\x00"      , "application/x-xz"           ),
+    (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (b"Rar!\x1a\x07"      , "application/vnd.rar"        ),
+    (b"ID3"               , "audio/mpeg"                 ),
+    (b"OggS"              , "audio/ogg"                  ),
+    (b"fLaC"              , "audio/flac"                 ),
+    (b"\x1a\x45\xdf\xa3"  , "video/webm"                 ),
+];
+
+// Guess MIME type of the file from its first bytes.
+pub fn sniff_mime_type<P: AsRef<Path>>(path: P) -> Option<&'static str> {
+    let mut head = [0u8; 8];
+    let length   = match File::open(path).and_then(|mut f| f.read(&mut head)) {
+        Ok(l)  => l,
+        Err(_) => return None,
+    };
+
+    MAGIC_NUMBERS
+        .iter()
+        .find(|&&(magic, _)| head[..length].starts_with(magic))
+        .map(|&(_, mime_type)| mime_type)
+}
+
+const VERSION_SEPARATOR: &'static str = "~";
+
+// Return "path" itself if it is not used, or "path~1", "path~2", ... otherwise.
+pub fn available_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    if fs::symlink_metadata(path).is_err() {
+        return path.to_path_buf();
+    }
+
+    let mut version = 1;
+    loop {
+        let mut candidate = path.as_os_str().to_os_string();
+        candidate.push(format!("{}{}", VERSION_SEPARATOR, version));
+
+        let candidate = PathBuf::from(candidate);
+        if fs::symlink_metadata(&candidate).is_err() {
+            return candidate;
+        }
+        version += 1;
+    }
+}
+
+// "rename" cannot move files across file systems.
+const EXDEV: i32 = 18;
+
+pub fn is_cross_device(e: &IoError) -> bool {
+    e.raw_os_error() == Some(EXDEV)
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME       : u64 = 0x100000001b3;
+
+pub fn hash_file<P: AsRef<Path>>(p: P) -> Result<u64, IoError> {
+    let mut f = try!(File::open(p));
+
+    let mut hash   = FNV_OFFSET_BASIS;
+    let mut buffer = [0; 8192];
+    loop {
+        let size = try!(f.read(&mut buffer));
+        if size == 0 {
+            break;
+        }
+        for byte in &buffer[..size] {
+            hash = (hash ^ *byte as u64).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    Ok(hash)
+}
+
+// Copy "from" to "to", verify the copy by its size and hash, and then remove "from".
+// The error kind is "InvalidData" if the verification fails, and "from" is left as it is.
+pub fn copy_and_remove<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> Result<(), IoError> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    let metadata   = try!(fs::symlink_metadata(from));
+
+    if metadata.file_type().is_symlink() {
+        try!(unix_fs::symlink(try!(fs::read_link(from)), to));
+        return fs::remove_file(from);
+    }
+
+    // "copy" also copies permission bits.
+    try!(fs::copy(from, to));
+
+    let verified = try!(fs::metadata(to)).len() == metadata.len() && try!(hash_file(from)) == try!(hash_file(to));
+    if !verified {
+        try!(fs::remove_file(to));
+        return Err(IoError::new(IoErrorKind::InvalidData, format!("Failed to verify the copy of \"{}\"", from.display())));
+    }
+
+    let times = FileTimes::new()
+        .set_accessed(try!(metadata.accessed()))
+        .set_modified(try!(metadata.modified()));
+    try!(try!(OpenOptions::new().write(true).open(to)).set_times(times));
+
+    fs::remove_file(from)
 }
 
-pub fn walk_dir<P: AsRef<Path>>(root: P) -> BTreeSet<String> {
+#[cfg(target_os = "linux")]
+mod xattr {
+    use std::ffi::CString;
+    use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+    use std::os::raw::{c_char, c_int, c_void};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+    use std::ptr;
+
+    // These are in the C library which is always linked, so that no crate is needed.
+    extern "C" {
+        fn llistxattr(path: *const c_char, list: *mut c_char, size: usize) -> isize;
+        fn lgetxattr(path: *const c_char, name: *const c_char, value: *mut c_void, size: usize) -> isize;
+        fn lsetxattr(path: *const c_char, name: *const c_char, value: *const c_void, size: usize, flags: c_int) -> c_int;
+    }
+
+    fn to_c_string<S: AsRef<[u8]>>(s: S) -> Result<CString, IoError> {
+        CString::new(s.as_ref()).map_err(|e| IoError::new(IoErrorKind::InvalidInput, e))
+    }
+
+    // Call twice, first to get the size and then to get the contents.
+    fn read_buffer<F: Fn(*mut c_void, usize) -> isize>(f: F) -> Result<Vec<u8>, IoError> {
+        let size = f(ptr::null_mut(), 0);
+        if size < 0 {
+            return Err(IoError::last_os_error());
+        }
+        let mut buffer = vec![0u8; size as usize];
+        let size       = f(buffer.as_mut_ptr() as *mut c_void, buffer.len());
+        if size < 0 {
+            return Err(IoError::last_os_error());
+        }
+        buffer.truncate(size as usize);
+        Ok(buffer)
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<(String, Vec<u8>)>, IoError> {
+        let path  = try!(to_c_string(path.as_ref().as_os_str().as_bytes()));
+        let names = try!(read_buffer(|buffer, size| unsafe { llistxattr(path.as_ptr(), buffer as *mut c_char, size) }));
+
+        let mut xattrs = Vec::new();
+        for name in names.split(|&b| b == 0).filter(|n| !n.is_empty()) {
+            let c_name = try!(to_c_string(name));
+            let value  = try!(read_buffer(|buffer, size| unsafe { lgetxattr(path.as_ptr(), c_name.as_ptr(), buffer, size) }));
+            if let Ok(name) = String::from_utf8(name.to_vec()) {
+                xattrs.push((name, value));
+            }
+        }
+        Ok(xattrs)
+    }
+
+    pub fn write<P: AsRef<Path>>(path: P, name: &str, value: &[u8]) -> Result<(), IoError> {
+        let path = try!(to_c_string(path.as_ref().as_os_str().as_bytes()));
+        let name = try!(to_c_string(name));
+        match unsafe { lsetxattr(path.as_ptr(), name.as_ptr(), value.as_ptr() as *const c_void, value.len(), 0) } {
+            0 => Ok(()),
+            _ => Err(IoError::last_os_error()),
+        }
+    }
+}
+#[cfg(not(target_os = "linux"))]
+mod xattr {
+    use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+    use std::path::Path;
+
+    pub fn read<P: AsRef<Path>>(_path: P) -> Result<Vec<(String, Vec<u8>)>, IoError> {
+        Ok(Vec::new())
+    }
+
+    pub fn write<P: AsRef<Path>>(_path: P, _name: &str, _value: &[u8]) -> Result<(), IoError> {
+        Err(IoError::new(IoErrorKind::Other, "Extended attributes are not supported"))
+    }
+}
+
+// Return no extended attribute if the filesystem does not support them.
+pub fn read_xattrs<P: AsRef<Path>>(path: P) -> Vec<(String, Vec<u8>)> {
+    xattr::read(path).unwrap_or(Vec::new())
+}
+
+// Metadata which "sweep" records and "restore" puts back, in addition to the contents.
+// Timestamps are compared in seconds, since "manifest.toml" records them in seconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attributes {
+    pub mode  : u32,
+    pub uid   : u32,
+    pub gid   : u32,
+    pub atime : i64,
+    pub mtime : i64,
+    pub xattrs: Vec<(String, Vec<u8>)>,
+}
+
+impl Attributes {
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, IoError> {
+        let path     = path.as_ref();
+        let metadata = try!(fs::symlink_metadata(path));
+
+        Ok(Attributes {
+            mode  : metadata.mode() & 0o7777,
+            uid   : metadata.uid(),
+            gid   : metadata.gid(),
+            atime : metadata.atime(),
+            mtime : metadata.mtime(),
+            xattrs: read_xattrs(path),
+        })
+    }
+
+    // Put back only what differs, and return what cannot be put back.
+    // Changing the owner may clear "setuid" bits, so the mode is put back after it, and the timestamps come last.
+    pub fn put_back<P: AsRef<Path>>(&self, path: P) -> Result<Vec<&'static str>, IoError> {
+        let path       = path.as_ref();
+        let current    = try!(Self::read(path));
+        let is_symlink = try!(fs::symlink_metadata(path)).file_type().is_symlink();
+        let mut lost   = Vec::new();
+
+        let failures = self.xattrs
+            .iter()
+            .filter(|x| !current.xattrs.contains(x))
+            .filter(|&&(ref name, ref value)| xattr::write(path, name, value).is_err())
+            .count();
+        if failures > 0 {
+            lost.push("extended attributes");
+        }
+
+        if (self.uid, self.gid) != (current.uid, current.gid) && unix_fs::lchown(path, Some(self.uid), Some(self.gid)).is_err() {
+            lost.push("owner");
+        }
+
+        if is_symlink {
+            return Ok(lost);
+        }
+
+        if self.mode != try!(fs::metadata(path)).mode() & 0o7777 && fs::set_permissions(path, fs::Permissions::from_mode(self.mode)).is_err() {
+            lost.push("mode");
+        }
+
+        if (self.atime, self.mtime) != (current.atime, current.mtime) {
+            let to_time = |secs: i64| if secs < 0 { UNIX_EPOCH } else { UNIX_EPOCH + StdDuration::from_secs(secs as u64) };
+            let times   = FileTimes::new()
+                .set_accessed(to_time(self.atime))
+                .set_modified(to_time(self.mtime));
+            // Directories cannot be opened for writing, but reading is enough to change timestamps.
+            if File::open(path).and_then(|f| f.set_times(times)).is_err() {
+                lost.push("timestamps");
+            }
+        }
+
+        Ok(lost)
+    }
+}
+
+pub fn walk_dir<P: AsRef<Path>>(root: P, hidden: &HiddenPolicy) -> BTreeSet<String> {
     let walker = WalkDir::new(root)
         .into_iter()
-        .filter_entry(|e| !e.is_hidden())
+        .filter_entry(|e| !hidden.is_skipped_name(e.file_name()))
         .filter_map(Result::ok)
         .filter(|e| !e.file_type().is_dir())
         .collect::<Vec<WalkDirEntry>>();
@@ -116,50 +540,154 @@ pub fn walk_dir<P: AsRef<Path>>(root: P) -> BTreeSet<String> {
         .collect::<BTreeSet<String>>()
 }
 
-pub fn potentially_empty_dirs<P: AsRef<Path>>(root: P, phantom_entries: Vec<PathBuf>) -> BTreeSet<PathBuf> {
-    fn potentially_empty_dirs(mut result: BTreeSet<PathBuf>, mut target_dirs: VecDeque<PathBuf>, phantom_entries: Vec<PathBuf>) -> BTreeSet<PathBuf> {
-        match target_dirs.pop_front() {
-            None => result,
-            Some(mut target_dir) => {
-                let read_result = fs::read_dir(&target_dir);
-                let ignore      = read_result.is_err();
-
-                let entries = match read_result {
-                    Ok(rd) => rd
-                        .filter_map(Result::ok)
-                        .collect::<Vec<DirEntry>>(),
-                    Err(_) => Vec::new(),
-                };
-                let include_file_or_hidden_dir = entries
-                    .iter()
-                    .filter(|e| phantom_entries.iter().all(|pe| *pe != e.path()))
-                    .any(|e| e.file_type().ok().map_or(true, |t| t.is_file()) || e.is_hidden());
-
-                if ignore || include_file_or_hidden_dir {
-                    loop {
-                        if !(result.remove(&target_dir) && target_dir.pop()) {
-                            break;
-                        }
-                    }
-                }
+const WALK_WORKERS    : usize = 8;
+const WALK_BUFFER_SIZE: usize = 1024;
+
+struct WalkQueue {
+    dirs: Vec<PathBuf>,
+    busy: usize,
+}
+
+struct Walker<S, V> {
+    queue : Mutex<WalkQueue>,
+    signal: Condvar,
+    hidden: HiddenPolicy,
+    prune : S,
+    visit : V,
+}
+
+impl<S, V, T> Walker<S, V>
+    where S: Fn(&str) -> bool, V: Fn(String) -> Option<T>
+{
+    fn next_dir(&self) -> Option<PathBuf> {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if let Some(dir) = queue.dirs.pop() {
+                queue.busy += 1;
+                return Some(dir);
+            }
+            if queue.busy == 0 {
+                return None;
+            }
+            queue = self.signal.wait(queue).unwrap();
+        }
+    }
+
+
+    // Return "false" if the receiver has gone.
+    fn walk_dir(&self, dir: &Path, sender: &SyncSender<Result<T, PathBuf>>, sub_dirs: &mut Vec<PathBuf>) -> bool {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_)      => return true,
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            if self.hidden.is_skipped_name(&entry.file_name()) {
+                continue;
+            }
+            let path = match entry.path().to_str() {
+                Some(p) => p.to_string(),
+                None    => continue,
+            };
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
 
-                let dirs = entries
-                    .iter()
-                    .filter(|e| e.file_type().ok().map_or(false, |t| t.is_dir()) && !e.is_hidden())
-                    .map(|e| e.path())
-                    .collect::<BTreeSet<PathBuf>>();
-                for dir in dirs.clone().into_iter() {
-                    result.insert(dir);
+            if is_dir {
+                if !(self.prune)(&path) {
+                    sub_dirs.push(PathBuf::from(path));
                 }
+            } else if let Some(result) = (self.visit)(path) {
+                if sender.send(Ok(result)).is_err() {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    fn work(&self, sender: SyncSender<Result<T, PathBuf>>) {
+        while let Some(dir) = self.next_dir() {
+            let mut busy = Busy { queue: &self.queue, signal: &self.signal, sender: &sender, dir: dir, sub_dirs: Vec::new() };
+            if !self.walk_dir(&busy.dir, &sender, &mut busy.sub_dirs) {
+                busy.sub_dirs.clear();
+            }
+        }
+    }
+}
 
-                let mut dirs = dirs.into_iter().collect::<VecDeque<PathBuf>>();
-                target_dirs.append(&mut dirs);
+// Finish a directory when dropped, so that the other workers never wait forever even if "visit" panics.
+// The rest of the directory is never visited then, so it is reported instead of being dropped silently.
+struct Busy<'a, T: 'a> {
+    queue   : &'a Mutex<WalkQueue>,
+    signal  : &'a Condvar,
+    sender  : &'a SyncSender<Result<T, PathBuf>>,
+    dir     : PathBuf,
+    sub_dirs: Vec<PathBuf>,
+}
 
-                potentially_empty_dirs(result, target_dirs, phantom_entries)
-            },
+impl<'a, T> Drop for Busy<'a, T> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.sender.send(Err(self.dir.clone())).ok();
         }
+
+        let mut queue = self.queue.lock().unwrap_or_else(PoisonError::into_inner);
+        queue.dirs.extend(self.sub_dirs.drain(..));
+        queue.busy -= 1;
+        self.signal.notify_all();
+    }
+}
+
+// Walk "root" with several threads, and stream what "visit" returns for each file (i.e. non-directory).
+// Directories for which "prune" returns "true" are not traversed at all, and ones abandoned because "visit" panics are streamed as errors.
+// Only the directories waiting to be read are kept, and the stream is bounded, so that memory usage does not grow with the number of files.
+// Unlike "walk_dir", the order of files is not sorted.
+pub fn walk_files_in_parallel<P, S, V, T>(root: P, hidden: HiddenPolicy, prune: S, visit: V) -> Receiver<Result<T, PathBuf>>
+    where P: AsRef<Path>,
+          S: Fn(&str) -> bool + Send + Sync + 'static,
+          V: Fn(String) -> Option<T> + Send + Sync + 'static,
+          T: Send + 'static
+{
+    let walker = Arc::new(Walker {
+        queue : Mutex::new(WalkQueue { dirs: vec![root.as_ref().to_path_buf()], busy: 0 }),
+        signal: Condvar::new(),
+        hidden: hidden,
+        prune : prune,
+        visit : visit,
+    });
+
+    let (sender, receiver) = mpsc::sync_channel(WALK_BUFFER_SIZE);
+    for _ in 0..WALK_WORKERS {
+        let (walker, sender) = (walker.clone(), sender.clone());
+        thread::spawn(move || walker.work(sender));
     }
 
+    receiver
+}
+
+// Like "sweep", this never walks into hidden directories nor across "boundary".
+pub fn find_files_by_name<P: AsRef<Path>>(root: P, file_name: &str, hidden: &HiddenPolicy, boundary: &Boundary) -> BTreeSet<PathBuf> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| {
+            if e.file_name() == file_name {
+                return true;
+            }
+            !hidden.is_skipped_name(e.file_name()) && !(e.depth() > 0 && e.file_type().is_dir() && boundary.is_crossed(e.path()))
+        })
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file() && e.file_name() == file_name)
+        .map(|e| e.path().to_path_buf())
+        .collect::<BTreeSet<PathBuf>>()
+}
+
+// Directories beyond "boundary" and ones for which "is_kept" returns "true" are regarded as non-empty,
+// so that neither they nor their parents are removed.
+// Lookups in "phantom_entries" must not be linear, and it is shared by all roots not to be copied, for large trees.
+pub fn potentially_empty_dirs<P, K>(root: P, phantom_entries: &BTreeSet<PathBuf>, hidden: &HiddenPolicy, boundary: &Boundary, is_kept: K) -> BTreeSet<PathBuf>
+    where P: AsRef<Path>, K: Fn(&Path) -> bool
+{
+    // The loop must not be recursive for large trees.
     let mut result     : BTreeSet<PathBuf> = BTreeSet::new();
     let mut target_dirs: VecDeque<PathBuf> = VecDeque::new();
 
@@ -167,7 +695,43 @@ pub fn potentially_empty_dirs<P: AsRef<Path>>(root: P, phantom_entries: Vec<Path
     result.insert(root.clone());
     target_dirs.push_back(root);
 
-    potentially_empty_dirs(result, target_dirs, phantom_entries)
+    while let Some(mut target_dir) = target_dirs.pop_front() {
+        let read_result = fs::read_dir(&target_dir);
+        let ignore      = read_result.is_err();
+
+        let entries = match read_result {
+            Ok(rd) => rd
+                .filter_map(Result::ok)
+                .collect::<Vec<DirEntry>>(),
+            Err(_) => Vec::new(),
+        };
+        let is_skipped_dir = |e: &DirEntry| hidden.is_skipped_name(&e.file_name()) || boundary.is_crossed(e.path()) || is_kept(&e.path());
+
+        let include_file_or_skipped_dir = entries
+            .iter()
+            .filter(|e| !phantom_entries.contains(&e.path()))
+            .any(|e| e.file_type().ok().map_or(true, |t| t.is_file()) || is_skipped_dir(e));
+
+        if ignore || include_file_or_skipped_dir {
+            loop {
+                if !(result.remove(&target_dir) && target_dir.pop()) {
+                    break;
+                }
+            }
+        }
+
+        let dirs = entries
+            .iter()
+            .filter(|e| e.file_type().ok().map_or(false, |t| t.is_dir()) && !is_skipped_dir(e))
+            .map(|e| e.path())
+            .collect::<BTreeSet<PathBuf>>();
+        for dir in dirs.into_iter() {
+            result.insert(dir.clone());
+            target_dirs.push_back(dir);
+        }
+    }
+
+    result
 }
 
 
@@ -302,6 +866,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn is_skipped_path_should_check_all_components() {
+        let hidden = HiddenPolicy::new(false, Vec::new());
+        assert!( hidden.is_skipped_path("./.a"));
+        assert!( hidden.is_skipped_path("./.a/b"));
+        assert!( hidden.is_skipped_path("./a/.b/c"));
+        assert!(!hidden.is_skipped_path("./a/b"));
+        assert!(!hidden.is_skipped_path("../a"));
+        assert!(!hidden.is_skipped_path("."));
+
+        let hidden = HiddenPolicy::new(true, vec![".git".to_string()]);
+        assert!(!hidden.is_skipped_path("./.a"));
+        assert!(!hidden.is_skipped_path("./a/.b/c"));
+        assert!( hidden.is_skipped_path("./.git/config"));
+        assert!( hidden.is_skipped_path("./.kic/ignore"));
+        assert!( hidden.is_skipped_path("./a/.kicignore"));
+    }
+
     #[test]
     fn supply_current_dir_prefix_should_return_added() {
         let path_names = [
@@ -393,7 +975,216 @@ mod tests {
             correct.insert(file.to_str().unwrap().to_string());
         }
 
-        assert_eq!(correct, walk_dir(helper.path_to_d1()));
+        assert_eq!(correct, walk_dir(helper.path_to_d1(), &HiddenPolicy::new(false, Vec::new())));
+
+        correct.insert(helper.path_to_f4().to_str().unwrap().to_string());
+        assert_eq!(correct, walk_dir(helper.path_to_d1(), &HiddenPolicy::new(true, Vec::new())));
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn find_files_by_name_should_return_b_tree_set() {
+        let helper = Helper::new("find_files_by_name_BTreeSet");
+        helper.create_dirs_and_files();
+
+        let mut correct = BTreeSet::new();
+        correct.insert(helper.path_to_f4());
+
+        let boundary = Boundary::new(helper.path_to_d1(), false);
+        assert_eq!(correct, find_files_by_name(helper.path_to_d1(), &helper.f4, &HiddenPolicy::new(false, Vec::new()), &boundary));
+        assert!(find_files_by_name(helper.path_to_d1(), "non_existing_file", &HiddenPolicy::new(false, Vec::new()), &boundary).is_empty());
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn find_files_by_name_should_stop_at_nested_root() {
+        let helper = Helper::new("find_files_by_name_nested_root");
+        helper.create_dirs_and_files();
+        fs::create_dir_all(helper.path_to_d3().join(WORKING_DIR_NAME)).ok();
+
+        let root     = helper.path_to_d1();
+        let boundary = Boundary::new(&root, false);
+        let mut correct = BTreeSet::new();
+        correct.insert(helper.path_to_f2());
+        assert_eq!(correct, find_files_by_name(&root, &helper.f2, &HiddenPolicy::new(false, Vec::new()), &boundary));
+        assert!(find_files_by_name(&root, &helper.f3, &HiddenPolicy::new(false, Vec::new()), &boundary).is_empty());
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn walk_files_in_parallel_should_return_same_files_as_walk_dir() {
+        let helper = Helper::new("walk_files_in_parallel");
+        helper.create_dirs_and_files();
+
+        let d1     = helper.path_to_d1().to_str().unwrap().to_string();
+        let hidden = HiddenPolicy::new(false, Vec::new());
+
+        let files = walk_files_in_parallel(&d1, hidden.clone(), |_| false, Some).iter().filter_map(Result::ok).collect::<BTreeSet<String>>();
+        assert_eq!(walk_dir(&d1, &hidden), files);
+
+        let pruned = helper.path_to_d3().to_str().unwrap().to_string();
+        let files  = walk_files_in_parallel(&d1, hidden, move |d| d == pruned, Some).iter().filter_map(Result::ok).collect::<BTreeSet<String>>();
+        let mut correct = BTreeSet::new();
+        correct.insert(helper.path_to_f1().to_str().unwrap().to_string());
+        correct.insert(helper.path_to_f2().to_str().unwrap().to_string());
+        assert_eq!(correct, files);
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn walk_files_in_parallel_should_not_hang_even_if_visit_panics() {
+        let helper = Helper::new("walk_files_in_parallel_panic");
+        helper.create_dirs_and_files();
+
+        let d1     = helper.path_to_d1().to_str().unwrap().to_string();
+        let f3     = helper.path_to_f3().to_str().unwrap().to_string();
+        let hidden = HiddenPolicy::new(false, Vec::new());
+
+        let visit = move |f: String| if f == f3 { panic!("Broken file") } else { Some(f) };
+        let (files, abandoned) = walk_files_in_parallel(&d1, hidden, |_| false, visit)
+            .iter()
+            .partition::<Vec<Result<String, PathBuf>>, _>(Result::is_ok);
+        let files = files.into_iter().filter_map(Result::ok).collect::<BTreeSet<String>>();
+        assert!(!files.contains(helper.path_to_f3().to_str().unwrap()));
+        assert!(files.contains(helper.path_to_f1().to_str().unwrap()));
+        assert!(files.contains(helper.path_to_f2().to_str().unwrap()));
+        assert_eq!(vec![Err(helper.path_to_d3())], abandoned);
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn sniff_mime_type_should_check_magic_number() {
+        let helper = Helper::new("sniff_mime_type");
+        helper.create_dirs_and_files();
+
+        File::create(helper.path_to_f2()).unwrap().write(b"%PDF-1.4\n").ok();
+        File::create(helper.path_to_f3()).unwrap().write(b"\x89PNG\r\n\x1a\n\x00").ok();
+
+        assert_eq!(None                   , sniff_mime_type(helper.path_to_f1()));
+        assert_eq!(Some("application/pdf"), sniff_mime_type(helper.path_to_f2()));
+        assert_eq!(Some("image/png")      , sniff_mime_type(helper.path_to_f3()));
+        assert_eq!(None                   , sniff_mime_type(helper.path_to_d1().join("not_existing_file")));
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn available_path_should_add_version_suffix() {
+        let helper = Helper::new("available_path_PathBuf");
+        helper.create_dirs_and_files();
+
+        let unused = helper.path_to_d1().join("unused_file");
+        assert_eq!(unused, available_path(&unused));
+
+        let mut first = helper.path_to_f1().into_os_string();
+        first.push("~1");
+        assert_eq!(PathBuf::from(&first), available_path(helper.path_to_f1()));
+
+        File::create(&first).unwrap();
+        let mut second = helper.path_to_f1().into_os_string();
+        second.push("~2");
+        assert_eq!(PathBuf::from(second), available_path(helper.path_to_f1()));
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn copy_and_remove_should_preserve_contents_permissions_and_times() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let helper = Helper::new("copy_and_remove_Ok");
+        helper.create_dirs_and_files();
+
+        let from = helper.path_to_f2();
+        let to   = helper.path_to_d5().join(&helper.f2);
+        fs::set_permissions(&from, fs::Permissions::from_mode(0o640)).unwrap();
+        let original = fs::metadata(&from).unwrap();
+        let hash     = hash_file(&from).unwrap();
+
+        copy_and_remove(&from, &to).unwrap();
+
+        let copied = fs::metadata(&to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(hash, hash_file(&to).unwrap());
+        assert_eq!(0o640, copied.permissions().mode() & 0o777);
+        assert_eq!(original.modified().unwrap(), copied.modified().unwrap());
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn put_back_should_restore_mode_timestamps_and_xattrs() {
+        let helper = Helper::new("put_back");
+        helper.create_dirs_and_files();
+
+        let path       = helper.path_to_f2();
+        let mut before = Attributes::read(&path).unwrap();
+        before.mode    = 0o600;
+        before.atime  -= 3600;
+        before.mtime  -= 7200;
+
+        assert!(before.put_back(&path).unwrap().is_empty());
+        assert_eq!(before, Attributes::read(&path).unwrap());
+
+        // Nothing is done if nothing differs.
+        assert!(before.put_back(&path).unwrap().is_empty());
+
+        // Extended attributes are checked only if the filesystem supports them.
+        if xattr::write(&path, "user.kic", b"value").is_ok() {
+            let before = Attributes::read(&path).unwrap();
+            assert!(before.xattrs.contains(&("user.kic".to_string(), b"value".to_vec())));
+
+            fs::remove_file(&path).ok();
+            File::create(&path).ok();
+            assert!(before.put_back(&path).unwrap().is_empty());
+            assert_eq!(before, Attributes::read(&path).unwrap());
+        }
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn protected_until_should_follow_timestamp_basis() {
+        let helper = Helper::new("protected_until_Option");
+        helper.create_dirs_and_files();
+
+        let bases = [TimestampBasis::Atime, TimestampBasis::Mtime, TimestampBasis::Ctime, TimestampBasis::Newest];
+        for basis in &bases {
+            assert!(protected_until(helper.path_to_f1(), Duration::weeks(1) , *basis).unwrap().is_some());
+            assert!(protected_until(helper.path_to_f1(), Duration::weeks(-1) , *basis).unwrap().is_none());
+        }
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn protected_until_should_return_error_for_broken_link() {
+        let helper = Helper::new("protected_until_Err");
+        helper.create_dirs_and_files();
+
+        let link = path_buf![&helper.path_to_d1(), "broken"];
+        unix_fs::symlink("not_exist", &link).unwrap();
+        assert!(protected_until(&link, Duration::weeks(1), TimestampBasis::Mtime).is_err());
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn is_in_use_should_find_opened_file() {
+        let helper = Helper::new("is_in_use");
+        helper.create_dirs_and_files();
+
+        let f = File::open(helper.path_to_f2()).unwrap();
+        let files_in_use = files_in_use();
+        assert!( is_in_use(helper.path_to_f2(), &files_in_use));
+        assert!(!is_in_use(helper.path_to_f3(), &files_in_use));
+        drop(f);
 
         helper.remove_dirs_and_files();
     }
@@ -403,11 +1194,12 @@ mod tests {
         let helper = Helper::new("potentially_empty_dirs_BTreeSet");
         helper.create_dirs_and_files();
 
-        let root = helper.path_to_d1();
+        let root     = helper.path_to_d1();
+        let boundary = Boundary::new(&root, false);
 
         let mut correct = BTreeSet::new();
         correct.insert(helper.path_to_d4());
-        let mut phantom_files = Vec::new();
+        let mut phantom_files = BTreeSet::new();
         let data_set = vec![
             (helper.path_to_d3(), helper.path_to_f3()),
             (helper.path_to_d2(), helper.path_to_f2()),
@@ -415,8 +1207,8 @@ mod tests {
         ];
         for (part_of_correct, phantom_file) in data_set.into_iter() {
             correct.insert(part_of_correct);
-            phantom_files.push(phantom_file);
-            assert_eq!(correct, potentially_empty_dirs(&root, phantom_files.clone()));
+            phantom_files.insert(phantom_file);
+            assert_eq!(correct, potentially_empty_dirs(&root, &phantom_files, &HiddenPolicy::new(false, Vec::new()), &boundary, |_| false));
         }
 
         correct.clear();
@@ -432,9 +1224,54 @@ mod tests {
             if let Some(file) = removed_file {
                 fs::remove_file(file).ok();
             }
-            assert_eq!(correct, potentially_empty_dirs(&root, Vec::new()));
+            assert_eq!(correct, potentially_empty_dirs(&root, &BTreeSet::new(), &HiddenPolicy::new(false, Vec::new()), &boundary, |_| false));
         }
 
+        // Hidden directories are also swept if they are included.
+        correct.insert(helper.path_to_d6());
+        assert_eq!(correct, potentially_empty_dirs(&root, &BTreeSet::new(), &HiddenPolicy::new(true, Vec::new()), &boundary, |_| false));
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn potentially_empty_dirs_should_stop_at_nested_root() {
+        let helper = Helper::new("potentially_empty_dirs_nested_root");
+        helper.create_dirs_and_files();
+        fs::remove_file(helper.path_to_f3()).ok();
+        fs::create_dir_all(helper.path_to_d3().join(WORKING_DIR_NAME)).ok();
+
+        let root     = helper.path_to_d1();
+        let boundary = Boundary::new(&root, true);
+        assert!(!boundary.is_crossed(helper.path_to_d2()));
+        assert!( boundary.is_crossed(helper.path_to_d3()));
+        assert!(!boundary.is_beyond(helper.path_to_f2()));
+        assert!( boundary.is_beyond(helper.path_to_d3()));
+        assert!( boundary.is_beyond(helper.path_to_d4()));
+
+        // Neither the nested root nor its parents are empty, even though it has no file except its settings.
+        let phantom_files = vec![helper.path_to_f1(), helper.path_to_f2(), helper.path_to_f4()].into_iter().collect();
+        let mut correct   = BTreeSet::new();
+        correct.insert(helper.path_to_d5());
+        assert_eq!(correct, potentially_empty_dirs(&root, &phantom_files, &HiddenPolicy::new(false, Vec::new()), &boundary, |_| false));
+
+        helper.remove_dirs_and_files();
+    }
+
+    #[test]
+    fn potentially_empty_dirs_should_not_return_kept_dirs() {
+        let helper = Helper::new("potentially_empty_dirs_kept");
+        helper.create_dirs_and_files();
+
+        let root          = helper.path_to_d1();
+        let boundary      = Boundary::new(&root, false);
+        let phantom_files = vec![helper.path_to_f1(), helper.path_to_f2(), helper.path_to_f3(), helper.path_to_f4()].into_iter().collect();
+        let kept_dir      = helper.path_to_d3();
+
+        let mut correct = BTreeSet::new();
+        correct.insert(helper.path_to_d5());
+        assert_eq!(correct, potentially_empty_dirs(&root, &phantom_files, &HiddenPolicy::new(false, Vec::new()), &boundary, |d| d == kept_dir));
+
         helper.remove_dirs_and_files();
     }
 }
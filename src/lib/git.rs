@@ -0,0 +1,56 @@
+use lib::fs::*;
+use lib::io::*;
+use std::collections::BTreeSet;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::process;
+use std::str;
+
+
+fn parse_ls_files(stdout: &[u8]) -> BTreeSet<String> {
+    stdout
+        .split(|b| *b == 0)
+        .filter(|p| !p.is_empty())
+        .filter_map(|p| str::from_utf8(p).ok())
+        .map(supply_current_dir_prefix)
+        .collect::<BTreeSet<String>>()
+}
+
+pub fn tracked_files() -> Result<BTreeSet<String>, IoError> {
+    print_with_tag(Tag::Info, "Read files tracked by \"git\"");
+
+    let result = process::Command::new("git")
+        .arg("ls-files")
+        .arg("-z")
+        .output();
+    let output = match result {
+        Ok(o)  => o,
+        Err(e) => match e.kind() {
+            IoErrorKind::NotFound => {
+                print_with_tag(Tag::Notice, "\"git\" is not installed");
+                return Ok(BTreeSet::new());
+            },
+            _ => return Err(e),
+        },
+    };
+
+    if !output.status.success() {
+        print_with_tag(Tag::Notice, "Current directory is not in \"git\" working tree");
+        return Ok(BTreeSet::new());
+    }
+
+    Ok(parse_ls_files(&output.stdout))
+}
+
+
+#[test]
+fn parse_ls_files_should_return_paths_with_current_dir_prefix() {
+    let stdout = b"a\0dir/b c\0dir/sub/d\0";
+
+    let mut correct = BTreeSet::new();
+    correct.insert("./a"        .to_string());
+    correct.insert("./dir/b c"  .to_string());
+    correct.insert("./dir/sub/d".to_string());
+
+    assert_eq!(correct, parse_ls_files(stdout));
+    assert!(parse_ls_files(b"").is_empty());
+}
@@ -15,6 +15,13 @@ pub fn read_line_from_stdin() -> Result<String, IoError>  {
         .map(|_| input.trim().to_string())
 }
 
+// "None" means the end of input, which is distinguished from an empty line.
+pub fn read_line_or_eof_from_stdin() -> Result<Option<String>, IoError>  {
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)
+        .map(|n| if n == 0 { None } else { Some(input.trim().to_string()) })
+}
+
 
 pub enum Tag {
     Info,
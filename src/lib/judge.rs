@@ -0,0 +1,190 @@
+extern crate chrono;
+
+use self::chrono::{DateTime, Duration, Local};
+
+use constant::{CONFIG_FILE_NAME, MAIN_DIR};
+use error::CliError;
+use lib::fs::*;
+use lib::git;
+use lib::rule::{Action, AttributeRules};
+use lib::setting::{Config, ConfigKey, IgnoreRules};
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::path::Path;
+
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    KeptByRule(String),
+    KeptByIgnore(String, String),
+    KeptByGit,
+    KeptAsMarker,
+    KeptForError(String),
+    Protected(DateTime<Local>),
+    DustByRule(String),
+    DustByIgnore(String, String),
+    Dust,
+}
+
+impl Verdict {
+    pub fn is_dust(&self) -> bool {
+        match *self {
+            Verdict::DustByRule(_) | Verdict::DustByIgnore(_, _) | Verdict::Dust => true,
+            _                                                                     => false,
+        }
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            Verdict::KeptByRule(ref key)                 => write!(f, "kept by \"{}\" in \"{}\"", key, CONFIG_FILE_NAME),
+            Verdict::KeptByIgnore(ref entry, ref origin) => write!(f, "kept by \"{}\" in {}", entry, origin),
+            Verdict::KeptByGit                           => write!(f, "kept because it is tracked by \"git\""),
+            Verdict::KeptAsMarker                        => write!(f, "kept as the marker of \"sweep.keep_marker\""),
+            Verdict::KeptForError(ref error)             => write!(f, "kept because its metadata cannot be read ({})", error),
+            Verdict::Protected(ref until)                => write!(f, "protected by \"sweep.moratorium\" until {}", until.format("%Y-%m-%d %H:%M:%S")),
+            Verdict::DustByRule(ref key)                 => write!(f, "dust by \"{}\" in \"{}\"", key, CONFIG_FILE_NAME),
+            Verdict::DustByIgnore(ref entry, ref origin) => write!(f, "dust by \"{}\" in {}", entry, origin),
+            Verdict::Dust                                => write!(f, "dust"),
+        }
+    }
+}
+
+
+// Both "sweep" and "ignore check" use this, so that they never disagree.
+#[derive(Debug)]
+pub struct Judge {
+    attribute_rules: AttributeRules,
+    ignore_rules   : IgnoreRules,
+    tracked_files  : BTreeSet<String>,
+    moratorium     : Option<Duration>,
+    timestamp      : TimestampBasis,
+    hidden         : HiddenPolicy,
+    empty_dirs     : EmptyDirPolicy,
+}
+
+impl Judge {
+    // "moratorium" is not applied if "all" is true.
+    // Only "ignore"s which affect paths in "roots" are read.
+    pub fn read<S: AsRef<str>>(config: &Config, all: bool, roots: &[S]) -> Result<Self, CliError> {
+        let moratorium = try!(config.get(ConfigKey::SweepMoratorium));
+        let moratorium = if all { None } else { Some(Config::to_duration(moratorium)) };
+        let timestamp  = TimestampBasis::from(try!(config.get(ConfigKey::SweepTimestamp)));
+
+        let keep_git_tracked = Config::to_bool(try!(config.get(ConfigKey::SweepKeepGitTracked)));
+        let tracked_files    = if keep_git_tracked { try!(git::tracked_files()) } else { BTreeSet::new() };
+
+        let attribute_rules = try!(AttributeRules::read(config));
+
+        let hidden     = try!(HiddenPolicy::read(config));
+        let empty_dirs = try!(EmptyDirPolicy::read(config));
+
+        let boundary     = try!(Boundary::read(config, MAIN_DIR));
+        let ignore_rules = try!(IgnoreRules::read(&hidden, &boundary, roots));
+
+        Ok(Judge {
+            attribute_rules: attribute_rules,
+            ignore_rules   : ignore_rules,
+            tracked_files  : tracked_files,
+            moratorium     : moratorium,
+            timestamp      : timestamp,
+            hidden         : hidden,
+            empty_dirs     : empty_dirs,
+        })
+    }
+
+    pub fn hidden(&self) -> &HiddenPolicy {
+        &self.hidden
+    }
+
+    pub fn empty_dirs(&self) -> &EmptyDirPolicy {
+        &self.empty_dirs
+    }
+
+    // An empty directory is kept by "ignore" as well as by its age.
+    pub fn is_kept_dir<P: AsRef<Path>>(&self, dir: P) -> bool {
+        let dir = dir.as_ref();
+        self.empty_dirs.is_too_young(dir) || dir.to_str().map_or(false, |d| self.ignore_rules.is_ignored(d))
+    }
+
+    // A directory kept by "ignore" is skipped as a whole unless something in it may be a "dust".
+    pub fn is_prunable<S: AsRef<str>>(&self, dir: S) -> bool {
+        let dir = dir.as_ref();
+        !self.attribute_rules.has_sweep_rules()
+            && self.ignore_rules.last_match(dir).map_or(false, |r| !r.is_negated())
+            && !self.ignore_rules.may_unignore_in(dir)
+    }
+
+    fn judge_by_ignore(&self, path: &str) -> Verdict {
+        match self.ignore_rules.last_match(path) {
+            Some(rule) if !rule.is_negated() => Verdict::KeptByIgnore(rule.entry().to_string(), rule.origin().to_string()),
+            Some(rule)                       => Verdict::DustByIgnore(rule.entry().to_string(), rule.origin().to_string()),
+            None                             => Verdict::Dust,
+        }
+    }
+
+    // Attribute rules in "config.toml" take precedence over "ignore", but not over the moratorium.
+    // Markers and files tracked by "git" are always kept, whatever the rules say.
+    pub fn judge<S: AsRef<str>>(&self, path: S) -> Verdict {
+        let path = path.as_ref();
+        if self.empty_dirs.is_marker(path) {
+            return Verdict::KeptAsMarker;
+        }
+        if self.tracked_files.contains(path) {
+            return Verdict::KeptByGit;
+        }
+
+        let verdict = match self.attribute_rules.judge(path) {
+            Some((Action::Keep , key)) => Verdict::KeptByRule(key.to_string()),
+            Some((Action::Sweep, key)) => Verdict::DustByRule(key.to_string()),
+            None                       => self.judge_by_ignore(path),
+        };
+        if !verdict.is_dust() {
+            return verdict;
+        }
+
+        let moratorium = match self.moratorium {
+            Some(ref m) => m,
+            None        => return verdict,
+        };
+        match protected_until(path, moratorium, self.timestamp) {
+            Ok(Some(until)) => Verdict::Protected(until.with_timezone(&Local)),
+            Ok(None)        => verdict,
+            Err(e)          => Verdict::KeptForError(e.to_string()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_judge(tracked: &[&str], sweep_extensions: &str) -> Judge {
+        Judge {
+            attribute_rules: AttributeRules::new(Vec::new(), vec![(ConfigKey::RulesSweepExtensions, sweep_extensions.to_string())]),
+            ignore_rules   : IgnoreRules::from_arguments(&vec!["./src".to_string()]).unwrap(),
+            tracked_files  : tracked.iter().map(|t| t.to_string()).collect(),
+            moratorium     : None,
+            timestamp      : TimestampBasis::Mtime,
+            hidden         : HiddenPolicy::new(false, Vec::new()),
+            empty_dirs     : EmptyDirPolicy::new(true, Duration::zero(), ".kickeep".to_string()),
+        }
+    }
+
+    #[test]
+    fn judge_should_keep_tracked_files_even_if_sweep_rule_matches() {
+        let judge = new_judge(&["./Cargo.toml"], "toml");
+        assert_eq!(Verdict::KeptByGit, judge.judge("./Cargo.toml"));
+
+        let judge = new_judge(&[], "toml");
+        assert_eq!(Verdict::DustByRule("rules.sweep_extensions".to_string()), judge.judge("./Cargo.toml"));
+    }
+
+    #[test]
+    fn judge_should_keep_tracked_files_even_if_negated_by_ignore() {
+        let judge = Judge { ignore_rules: IgnoreRules::from_arguments(&vec!["!./Cargo.toml".to_string()]).unwrap(), ..new_judge(&["./Cargo.toml"], "") };
+        assert_eq!(Verdict::KeptByGit, judge.judge("./Cargo.toml"));
+    }
+}
@@ -1,5 +1,8 @@
 #[macro_use]
 pub mod fs;
 
+pub mod git;
 pub mod io;
+pub mod judge;
+pub mod rule;
 pub mod setting;
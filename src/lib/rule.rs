@@ -0,0 +1,347 @@
+use error::ConfigError;
+use lib::fs::sniff_mime_type;
+use lib::setting::{Config, ConfigKey};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::{self, Metadata};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    Keep,
+    Sweep,
+}
+
+#[derive(Debug)]
+enum Condition {
+    LargerThan(u64),
+    SmallerThan(u64),
+    Extensions(Vec<String>),
+    Owners(Vec<u32>),
+    Mode(u32),
+}
+
+fn has_extension<P: AsRef<Path>>(path: P, extensions: &Vec<String>) -> bool {
+    let file_name = match path.as_ref().file_name().and_then(|n| n.to_str()) {
+        Some(n) => n,
+        None    => return false,
+    };
+    // Compare with the whole suffix so that multi-part extensions like "tar.gz" also work.
+    extensions
+        .iter()
+        .any(|e| file_name.len() > e.len() + 1 && file_name.ends_with(&format!(".{}", e)))
+}
+
+impl Condition {
+    fn from(key: &ConfigKey, value: String) -> Option<Self> {
+        if value.is_empty() {
+            return None;
+        }
+
+        Some(match *key {
+            ConfigKey::RulesKeepLargerThan   => Condition::LargerThan(Config::to_bytes(value)),
+            ConfigKey::RulesSweepSmallerThan => Condition::SmallerThan(Config::to_bytes(value)),
+            ConfigKey::RulesKeepExtensions   |
+            ConfigKey::RulesSweepExtensions  => Condition::Extensions(Config::to_list(value)),
+            ConfigKey::RulesKeepOwners       |
+            ConfigKey::RulesSweepOwners      => Condition::Owners(Config::to_uids(value)),
+            ConfigKey::RulesKeepMode         |
+            ConfigKey::RulesSweepMode        => Condition::Mode(Config::to_mode(value)),
+            _                                => unreachable!("Wrong to use this function!!"),
+        })
+    }
+
+    fn is_match<P: AsRef<Path>>(&self, path: P, metadata: &Metadata) -> bool {
+        match *self {
+            Condition::LargerThan(bytes)    => metadata.len() > bytes,
+            Condition::SmallerThan(bytes)   => metadata.len() < bytes,
+            Condition::Extensions(ref exts) => has_extension(path, exts),
+            Condition::Owners(ref uids)     => uids.contains(&metadata.uid()),
+            Condition::Mode(bits)           => metadata.mode() & bits != 0,
+        }
+    }
+}
+
+
+#[derive(Debug)]
+pub struct AttributeRules {
+    keep : Vec<(ConfigKey, Condition)>,
+    sweep: Vec<(ConfigKey, Condition)>,
+}
+
+impl AttributeRules {
+    fn conditions(values: Vec<(ConfigKey, String)>) -> Vec<(ConfigKey, Condition)> {
+        values
+            .into_iter()
+            .filter_map(|(key, value)| Condition::from(&key, value).map(|c| (key, c)))
+            .collect()
+    }
+
+    fn values(config: &Config, keys: Vec<ConfigKey>) -> Result<Vec<(ConfigKey, String)>, ConfigError> {
+        let mut values = Vec::new();
+        for key in keys {
+            let value = try!(config.get(&key));
+            values.push((key, value));
+        }
+        Ok(values)
+    }
+
+    // Each value must be valid for its key, as validated by "Config". Empty values are no rule.
+    pub fn new(keep: Vec<(ConfigKey, String)>, sweep: Vec<(ConfigKey, String)>) -> Self {
+        AttributeRules { keep: Self::conditions(keep), sweep: Self::conditions(sweep) }
+    }
+
+    pub fn read(config: &Config) -> Result<Self, ConfigError> {
+        let keep = try!(Self::values(config, vec![
+            ConfigKey::RulesKeepLargerThan,
+            ConfigKey::RulesKeepExtensions,
+            ConfigKey::RulesKeepOwners,
+            ConfigKey::RulesKeepMode,
+        ]));
+        let sweep = try!(Self::values(config, vec![
+            ConfigKey::RulesSweepSmallerThan,
+            ConfigKey::RulesSweepExtensions,
+            ConfigKey::RulesSweepOwners,
+            ConfigKey::RulesSweepMode,
+        ]));
+
+        Ok(Self::new(keep, sweep))
+    }
+
+    pub fn has_sweep_rules(&self) -> bool {
+        !self.sweep.is_empty()
+    }
+
+    fn find<'a>(conditions: &'a Vec<(ConfigKey, Condition)>, path: &Path, metadata: &Metadata) -> Option<&'a str> {
+        conditions
+            .iter()
+            .find(|&&(_, ref c)| c.is_match(path, metadata))
+            .map(|&(ref k, _)| k.to_str())
+    }
+
+    // "keep" rules always take precedence over "sweep" rules.
+    // "None" means that no rule decides, so "ignore" decides instead.
+    pub fn judge<P: AsRef<Path>>(&self, path: P) -> Option<(Action, &str)> {
+        let path     = path.as_ref();
+        let metadata = match fs::metadata(path) {
+            Ok(m)  => m,
+            Err(_) => return None,
+        };
+
+        match (Self::find(&self.keep, path, &metadata), Self::find(&self.sweep, path, &metadata)) {
+            (Some(key), _        ) => Some((Action::Keep , key)),
+            (None     , Some(key)) => Some((Action::Sweep, key)),
+            (None     , None     ) => None,
+        }
+    }
+}
+
+
+#[derive(Debug, PartialEq)]
+pub enum Limit {
+    MinSize,
+    MaxSize,
+    MaxTotalBytes,
+}
+
+impl Display for Limit {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            Limit::MinSize       => write!(f, "smaller than \"{}\"", ConfigKey::SweepMinSize.to_str()),
+            Limit::MaxSize       => write!(f, "larger than \"{}\"" , ConfigKey::SweepMaxSize.to_str()),
+            Limit::MaxTotalBytes => write!(f, "over \"{}\""        , ConfigKey::SweepMaxTotalBytes.to_str()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SizeLimits {
+    min_size       : Option<u64>,
+    max_size       : Option<u64>,
+    max_total_bytes: Option<u64>,
+}
+
+impl SizeLimits {
+    pub fn read(config: &Config) -> Result<Self, ConfigError> {
+        let bytes = |key: ConfigKey| config.get(key).map(|v| if v.is_empty() { None } else { Some(Config::to_bytes(v)) });
+
+        Ok(SizeLimits {
+            min_size       : try!(bytes(ConfigKey::SweepMinSize)),
+            max_size       : try!(bytes(ConfigKey::SweepMaxSize)),
+            max_total_bytes: try!(bytes(ConfigKey::SweepMaxTotalBytes)),
+        })
+    }
+
+    fn limit_of_size(&self, size: u64) -> Option<Limit> {
+        match (self.min_size, self.max_size) {
+            (Some(min), _) if size < min => Some(Limit::MinSize),
+            (_, Some(max)) if size > max => Some(Limit::MaxSize),
+            _                            => None,
+        }
+    }
+
+    // The limit which skips the "dust" by itself. "max_total_bytes" depends on the other "dust"s in the run, so it is not checked.
+    pub fn limit_of<P: AsRef<Path>>(&self, path: P) -> Option<Limit> {
+        self.limit_of_size(fs::symlink_metadata(path).map(|m| m.len()).unwrap_or(0))
+    }
+
+    // Split "dust"s into ones to be moved and ones to be skipped with the limit.
+    // Once the total reaches "max_total_bytes", all the rest are skipped.
+    pub fn apply<I: IntoIterator<Item = String>>(&self, paths: I) -> (Vec<String>, Vec<(String, Limit)>) {
+        let mut accepted = Vec::new();
+        let mut skipped  = Vec::new();
+        let mut total    = 0u64;
+        let mut is_full  = false;
+
+        for path in paths {
+            let size = fs::symlink_metadata(&path).map(|m| m.len()).unwrap_or(0);
+
+            let limit = match (self.limit_of_size(size), self.max_total_bytes) {
+                (Some(limit), _                                  ) => Some(limit),
+                (None, Some(cap)) if is_full || total + size > cap => Some(Limit::MaxTotalBytes),
+                _                                                  => None,
+            };
+
+            match limit {
+                Some(limit) => {
+                    is_full = is_full || limit == Limit::MaxTotalBytes;
+                    skipped.push((path, limit));
+                },
+                None => {
+                    total += size;
+                    accepted.push(path);
+                },
+            }
+        }
+
+        (accepted, skipped)
+    }
+}
+
+
+fn is_mime_type_match(pattern: &str, mime_type: &str) -> bool {
+    if pattern.ends_with("/*") {
+        mime_type.starts_with(&pattern[..pattern.len() - 1])
+    } else {
+        pattern == mime_type
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    name      : String,
+    extensions: Vec<String>,
+    mime_types: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Buckets {
+    buckets: Vec<Bucket>,
+}
+
+impl Buckets {
+    pub fn read(config: &Config) -> Result<Self, ConfigError> {
+        let buckets = Config::to_buckets(try!(config.get(ConfigKey::SweepBuckets)))
+            .into_iter()
+            .map(|(name, items)| {
+                let (mime_types, extensions) = items.into_iter().partition(|i| i.contains('/'));
+                Bucket { name: name, extensions: extensions, mime_types: mime_types }
+            })
+            .collect::<Vec<Bucket>>();
+
+        Ok(Buckets { buckets: buckets })
+    }
+
+    // The first bucket which matches decides, and MIME type is sniffed only if any bucket needs it.
+    pub fn bucket_of<P: AsRef<Path>>(&self, path: P) -> Option<&str> {
+        let path          = path.as_ref();
+        let mut mime_type = None;
+
+        for bucket in &self.buckets {
+            if has_extension(path, &bucket.extensions) {
+                return Some(&bucket.name);
+            }
+            if bucket.mime_types.is_empty() {
+                continue;
+            }
+            if let Some(t) = *mime_type.get_or_insert_with(|| sniff_mime_type(path)) {
+                if bucket.mime_types.iter().any(|m| is_mime_type_match(m, t)) {
+                    return Some(&bucket.name);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+
+#[test]
+fn has_extension_should_compare_whole_suffix() {
+    let extensions = vec!["part".to_string(), "tar.gz".to_string()];
+
+    assert!( has_extension("./a.part"      , &extensions));
+    assert!( has_extension("./dir/b.tar.gz", &extensions));
+    assert!(!has_extension("./c.gz"        , &extensions));
+    assert!(!has_extension("./.part"       , &extensions));
+    assert!(!has_extension("./part"        , &extensions));
+    assert!(!has_extension("./d.part/e.txt", &extensions));
+}
+
+#[test]
+fn judge_should_prefer_keep_rules_to_sweep_rules() {
+    let rules = AttributeRules {
+        keep : vec![(ConfigKey::RulesKeepExtensions, Condition::Extensions(vec!["lock".to_string()]))],
+        sweep: vec![
+            (ConfigKey::RulesSweepSmallerThan, Condition::SmallerThan(u64::max_value())),
+            (ConfigKey::RulesSweepMode       , Condition::Mode(0o7777)),
+        ],
+    };
+
+    assert_eq!(Some((Action::Keep , "rules.keep_extensions"   )), rules.judge("./Cargo.lock"));
+    assert_eq!(Some((Action::Sweep, "rules.sweep_smaller_than")), rules.judge("./Cargo.toml"));
+    assert_eq!(None, rules.judge("./not-existing-file"));
+
+    let rules = AttributeRules { keep: Vec::new(), sweep: vec![(ConfigKey::RulesSweepMode, Condition::Mode(0o7777))] };
+    assert_eq!(Some((Action::Sweep, "rules.sweep_mode")), rules.judge("./Cargo.toml"));
+}
+
+#[test]
+fn apply_should_skip_files_with_limits() {
+    let size  = |p: &str| fs::metadata(p).unwrap().len();
+    let paths = || vec!["./src/main.rs".to_string(), "./src/constant.rs".to_string(), "./src/error.rs".to_string()];
+
+    let limits = SizeLimits { min_size: Some(size("./src/main.rs") + 1), max_size: Some(size("./src/error.rs") - 1), max_total_bytes: None };
+    assert_eq!(
+        (vec!["./src/constant.rs".to_string()], vec![("./src/main.rs".to_string(), Limit::MinSize), ("./src/error.rs".to_string(), Limit::MaxSize)]),
+        limits.apply(paths())
+    );
+    assert_eq!(Some(Limit::MinSize), limits.limit_of("./src/main.rs"));
+    assert_eq!(None                , limits.limit_of("./src/constant.rs"));
+
+    let limits = SizeLimits { min_size: None, max_size: None, max_total_bytes: Some(size("./src/main.rs") + size("./src/constant.rs") - 1) };
+    assert_eq!(
+        (vec!["./src/main.rs".to_string()], vec![("./src/constant.rs".to_string(), Limit::MaxTotalBytes), ("./src/error.rs".to_string(), Limit::MaxTotalBytes)]),
+        limits.apply(paths())
+    );
+    assert_eq!(None, limits.limit_of("./src/error.rs"));
+}
+
+#[test]
+fn bucket_of_should_return_first_matched_bucket() {
+    let buckets = Buckets {
+        buckets: vec![
+            Bucket { name: "images"  .to_string(), extensions: vec!["png".to_string()], mime_types: vec!["image/*".to_string()] },
+            Bucket { name: "codes"   .to_string(), extensions: vec!["rs".to_string()] , mime_types: Vec::new() },
+            Bucket { name: "all_rust".to_string(), extensions: vec!["rs".to_string()] , mime_types: Vec::new() },
+        ],
+    };
+
+    assert_eq!(Some("codes"), buckets.bucket_of("./src/main.rs"));
+    assert_eq!(None         , buckets.bucket_of("./Cargo.toml"));
+
+    assert!( is_mime_type_match("image/*"  , "image/png"));
+    assert!( is_mime_type_match("image/png", "image/png"));
+    assert!(!is_mime_type_match("image/*"  , "application/pdf"));
+}
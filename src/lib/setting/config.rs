@@ -17,15 +17,61 @@ use std::path::PathBuf;
 use std::str::FromStr;
 
 
-const CONFIG_KEY_BURN_MORATORIUM : &'static str = "burn.moratorium";
-const CONFIG_KEY_SWEEP_MORATORIUM: &'static str = "sweep.moratorium";
-const CONFIG_KEY_SWEEP_PERIOD    : &'static str = "sweep.period";
-const CONFIG_KEY_SWEEP_TIME      : &'static str = "sweep.time";
-
-const CONFIG_DEFAULT_VALUE_BURN_MORATORIUM : &'static str = "2 weeks";
-const CONFIG_DEFAULT_VALUE_SWEEP_MORATORIUM: &'static str = "10 minutes";
-const CONFIG_DEFAULT_VALUE_SWEEP_PERIOD    : &'static str = "daily";
-const CONFIG_DEFAULT_VALUE_SWEEP_TIME      : &'static str = "00:00";
+const CONFIG_KEY_BURN_MORATORIUM         : &'static str = "burn.moratorium";
+const CONFIG_KEY_SWEEP_MORATORIUM        : &'static str = "sweep.moratorium";
+const CONFIG_KEY_SWEEP_PERIOD            : &'static str = "sweep.period";
+const CONFIG_KEY_SWEEP_TIME              : &'static str = "sweep.time";
+const CONFIG_KEY_SWEEP_KEEP_GIT_TRACKED  : &'static str = "sweep.keep_git_tracked";
+const CONFIG_KEY_SWEEP_TIMESTAMP         : &'static str = "sweep.timestamp";
+const CONFIG_KEY_SWEEP_INCLUDE_HIDDEN    : &'static str = "sweep.include_hidden";
+const CONFIG_KEY_SWEEP_HIDDEN_EXCLUSIONS : &'static str = "sweep.hidden_exclusions";
+const CONFIG_KEY_SWEEP_ONE_FILE_SYSTEM   : &'static str = "sweep.one_file_system";
+const CONFIG_KEY_SWEEP_EMPTY_DIRS        : &'static str = "sweep.empty_dirs";
+const CONFIG_KEY_SWEEP_EMPTY_DIRS_MIN_AGE: &'static str = "sweep.empty_dirs_min_age";
+const CONFIG_KEY_SWEEP_KEEP_MARKER       : &'static str = "sweep.keep_marker";
+const CONFIG_KEY_SWEEP_MIN_SIZE          : &'static str = "sweep.min_size";
+const CONFIG_KEY_SWEEP_MAX_SIZE          : &'static str = "sweep.max_size";
+const CONFIG_KEY_SWEEP_MAX_TOTAL_BYTES   : &'static str = "sweep.max_total_bytes";
+const CONFIG_KEY_SWEEP_BUCKETS           : &'static str = "sweep.buckets";
+const CONFIG_KEY_RULES_KEEP_LARGER_THAN  : &'static str = "rules.keep_larger_than";
+const CONFIG_KEY_RULES_KEEP_EXTENSIONS   : &'static str = "rules.keep_extensions";
+const CONFIG_KEY_RULES_KEEP_OWNERS       : &'static str = "rules.keep_owners";
+const CONFIG_KEY_RULES_KEEP_MODE         : &'static str = "rules.keep_mode";
+const CONFIG_KEY_RULES_SWEEP_SMALLER_THAN: &'static str = "rules.sweep_smaller_than";
+const CONFIG_KEY_RULES_SWEEP_EXTENSIONS  : &'static str = "rules.sweep_extensions";
+const CONFIG_KEY_RULES_SWEEP_OWNERS      : &'static str = "rules.sweep_owners";
+const CONFIG_KEY_RULES_SWEEP_MODE        : &'static str = "rules.sweep_mode";
+
+const CONFIG_DEFAULT_VALUE_BURN_MORATORIUM        : &'static str = "2 weeks";
+const CONFIG_DEFAULT_VALUE_SWEEP_MORATORIUM       : &'static str = "10 minutes";
+const CONFIG_DEFAULT_VALUE_SWEEP_PERIOD           : &'static str = "daily";
+const CONFIG_DEFAULT_VALUE_SWEEP_TIME             : &'static str = "00:00";
+const CONFIG_DEFAULT_VALUE_SWEEP_KEEP_GIT_TRACKED : &'static str = "false";
+const CONFIG_DEFAULT_VALUE_SWEEP_TIMESTAMP        : &'static str = "atime";
+const CONFIG_DEFAULT_VALUE_SWEEP_INCLUDE_HIDDEN   : &'static str = "false";
+const CONFIG_DEFAULT_VALUE_SWEEP_HIDDEN_EXCLUSIONS: &'static str = ".git, .kic, .kickeep";
+const CONFIG_DEFAULT_VALUE_SWEEP_ONE_FILE_SYSTEM  : &'static str = "false";
+const CONFIG_DEFAULT_VALUE_SWEEP_EMPTY_DIRS       : &'static str = "true";
+const CONFIG_DEFAULT_VALUE_SWEEP_EMPTY_DIR_AGE    : &'static str = "0 minutes";
+const CONFIG_DEFAULT_VALUE_SWEEP_KEEP_MARKER      : &'static str = ".kickeep";
+// An empty value disables the limit.
+const CONFIG_DEFAULT_VALUE_SWEEP_SIZE_LIMITS      : &'static str = "";
+// An empty value puts all "dust"s in "dusts" as they are.
+const CONFIG_DEFAULT_VALUE_SWEEP_BUCKETS          : &'static str = "";
+// An empty value disables the rule.
+const CONFIG_DEFAULT_VALUE_RULES                  : &'static str = "";
+
+const SIZE_UNITS: [(&'static str, u64); 9] = [
+    ("B"  , 1                        ),
+    ("KB" , 1000                     ),
+    ("KiB", 1024                     ),
+    ("MB" , 1000 * 1000              ),
+    ("MiB", 1024 * 1024              ),
+    ("GB" , 1000 * 1000 * 1000       ),
+    ("GiB", 1024 * 1024 * 1024       ),
+    ("TB" , 1000 * 1000 * 1000 * 1000),
+    ("TiB", 1024 * 1024 * 1024 * 1024),
+];
 
 
 #[derive(Debug)]
@@ -34,25 +80,112 @@ pub enum ConfigKey {
     SweepMoratorium,
     SweepPeriod,
     SweepTime,
+    SweepKeepGitTracked,
+    SweepTimestamp,
+    SweepIncludeHidden,
+    SweepHiddenExclusions,
+    SweepOneFileSystem,
+    SweepEmptyDirs,
+    SweepEmptyDirsMinAge,
+    SweepKeepMarker,
+    SweepMinSize,
+    SweepMaxSize,
+    SweepMaxTotalBytes,
+    SweepBuckets,
+    RulesKeepLargerThan,
+    RulesKeepExtensions,
+    RulesKeepOwners,
+    RulesKeepMode,
+    RulesSweepSmallerThan,
+    RulesSweepExtensions,
+    RulesSweepOwners,
+    RulesSweepMode,
 }
 
 impl ConfigKey {
     pub fn from<S: AsRef<str>>(key: S) -> Result<ConfigKey, ConfigError> {
         match key.as_ref().trim() {
-            CONFIG_KEY_BURN_MORATORIUM  => Ok(ConfigKey::BurnMoratorium),
-            CONFIG_KEY_SWEEP_MORATORIUM => Ok(ConfigKey::SweepMoratorium),
-            CONFIG_KEY_SWEEP_PERIOD     => Ok(ConfigKey::SweepPeriod),
-            CONFIG_KEY_SWEEP_TIME       => Ok(ConfigKey::SweepTime),
-            _                           => Err(ConfigError::new(ConfigErrorKind::InvalidKey)),
+            CONFIG_KEY_BURN_MORATORIUM          => Ok(ConfigKey::BurnMoratorium),
+            CONFIG_KEY_SWEEP_MORATORIUM         => Ok(ConfigKey::SweepMoratorium),
+            CONFIG_KEY_SWEEP_PERIOD             => Ok(ConfigKey::SweepPeriod),
+            CONFIG_KEY_SWEEP_TIME               => Ok(ConfigKey::SweepTime),
+            CONFIG_KEY_SWEEP_KEEP_GIT_TRACKED   => Ok(ConfigKey::SweepKeepGitTracked),
+            CONFIG_KEY_SWEEP_TIMESTAMP          => Ok(ConfigKey::SweepTimestamp),
+            CONFIG_KEY_SWEEP_INCLUDE_HIDDEN     => Ok(ConfigKey::SweepIncludeHidden),
+            CONFIG_KEY_SWEEP_HIDDEN_EXCLUSIONS  => Ok(ConfigKey::SweepHiddenExclusions),
+            CONFIG_KEY_SWEEP_ONE_FILE_SYSTEM    => Ok(ConfigKey::SweepOneFileSystem),
+            CONFIG_KEY_SWEEP_EMPTY_DIRS         => Ok(ConfigKey::SweepEmptyDirs),
+            CONFIG_KEY_SWEEP_EMPTY_DIRS_MIN_AGE => Ok(ConfigKey::SweepEmptyDirsMinAge),
+            CONFIG_KEY_SWEEP_KEEP_MARKER        => Ok(ConfigKey::SweepKeepMarker),
+            CONFIG_KEY_SWEEP_MIN_SIZE           => Ok(ConfigKey::SweepMinSize),
+            CONFIG_KEY_SWEEP_MAX_SIZE           => Ok(ConfigKey::SweepMaxSize),
+            CONFIG_KEY_SWEEP_MAX_TOTAL_BYTES    => Ok(ConfigKey::SweepMaxTotalBytes),
+            CONFIG_KEY_SWEEP_BUCKETS            => Ok(ConfigKey::SweepBuckets),
+            CONFIG_KEY_RULES_KEEP_LARGER_THAN   => Ok(ConfigKey::RulesKeepLargerThan),
+            CONFIG_KEY_RULES_KEEP_EXTENSIONS    => Ok(ConfigKey::RulesKeepExtensions),
+            CONFIG_KEY_RULES_KEEP_OWNERS        => Ok(ConfigKey::RulesKeepOwners),
+            CONFIG_KEY_RULES_KEEP_MODE          => Ok(ConfigKey::RulesKeepMode),
+            CONFIG_KEY_RULES_SWEEP_SMALLER_THAN => Ok(ConfigKey::RulesSweepSmallerThan),
+            CONFIG_KEY_RULES_SWEEP_EXTENSIONS   => Ok(ConfigKey::RulesSweepExtensions),
+            CONFIG_KEY_RULES_SWEEP_OWNERS       => Ok(ConfigKey::RulesSweepOwners),
+            CONFIG_KEY_RULES_SWEEP_MODE         => Ok(ConfigKey::RulesSweepMode),
+            _                                   => Err(ConfigError::new(ConfigErrorKind::InvalidKey)),
+        }
+    }
+
+    pub fn to_str(&self) -> &str {
+        match *self {
+            ConfigKey::BurnMoratorium        => CONFIG_KEY_BURN_MORATORIUM,
+            ConfigKey::SweepMoratorium       => CONFIG_KEY_SWEEP_MORATORIUM,
+            ConfigKey::SweepPeriod           => CONFIG_KEY_SWEEP_PERIOD,
+            ConfigKey::SweepTime             => CONFIG_KEY_SWEEP_TIME,
+            ConfigKey::SweepKeepGitTracked   => CONFIG_KEY_SWEEP_KEEP_GIT_TRACKED,
+            ConfigKey::SweepTimestamp        => CONFIG_KEY_SWEEP_TIMESTAMP,
+            ConfigKey::SweepIncludeHidden    => CONFIG_KEY_SWEEP_INCLUDE_HIDDEN,
+            ConfigKey::SweepHiddenExclusions => CONFIG_KEY_SWEEP_HIDDEN_EXCLUSIONS,
+            ConfigKey::SweepOneFileSystem    => CONFIG_KEY_SWEEP_ONE_FILE_SYSTEM,
+            ConfigKey::SweepEmptyDirs        => CONFIG_KEY_SWEEP_EMPTY_DIRS,
+            ConfigKey::SweepEmptyDirsMinAge  => CONFIG_KEY_SWEEP_EMPTY_DIRS_MIN_AGE,
+            ConfigKey::SweepKeepMarker       => CONFIG_KEY_SWEEP_KEEP_MARKER,
+            ConfigKey::SweepMinSize          => CONFIG_KEY_SWEEP_MIN_SIZE,
+            ConfigKey::SweepMaxSize          => CONFIG_KEY_SWEEP_MAX_SIZE,
+            ConfigKey::SweepMaxTotalBytes    => CONFIG_KEY_SWEEP_MAX_TOTAL_BYTES,
+            ConfigKey::SweepBuckets          => CONFIG_KEY_SWEEP_BUCKETS,
+            ConfigKey::RulesKeepLargerThan   => CONFIG_KEY_RULES_KEEP_LARGER_THAN,
+            ConfigKey::RulesKeepExtensions   => CONFIG_KEY_RULES_KEEP_EXTENSIONS,
+            ConfigKey::RulesKeepOwners       => CONFIG_KEY_RULES_KEEP_OWNERS,
+            ConfigKey::RulesKeepMode         => CONFIG_KEY_RULES_KEEP_MODE,
+            ConfigKey::RulesSweepSmallerThan => CONFIG_KEY_RULES_SWEEP_SMALLER_THAN,
+            ConfigKey::RulesSweepExtensions  => CONFIG_KEY_RULES_SWEEP_EXTENSIONS,
+            ConfigKey::RulesSweepOwners      => CONFIG_KEY_RULES_SWEEP_OWNERS,
+            ConfigKey::RulesSweepMode        => CONFIG_KEY_RULES_SWEEP_MODE,
         }
     }
 
-    fn to_str(&self) -> &str {
+    // Optional keys may be absent from "config.toml" which was created by older versions.
+    fn default_value(&self) -> Option<&str> {
         match *self {
-            ConfigKey::BurnMoratorium  => CONFIG_KEY_BURN_MORATORIUM,
-            ConfigKey::SweepMoratorium => CONFIG_KEY_SWEEP_MORATORIUM,
-            ConfigKey::SweepPeriod     => CONFIG_KEY_SWEEP_PERIOD,
-            ConfigKey::SweepTime       => CONFIG_KEY_SWEEP_TIME,
+            ConfigKey::SweepKeepGitTracked   => Some(CONFIG_DEFAULT_VALUE_SWEEP_KEEP_GIT_TRACKED),
+            ConfigKey::SweepTimestamp        => Some(CONFIG_DEFAULT_VALUE_SWEEP_TIMESTAMP),
+            ConfigKey::SweepIncludeHidden    => Some(CONFIG_DEFAULT_VALUE_SWEEP_INCLUDE_HIDDEN),
+            ConfigKey::SweepHiddenExclusions => Some(CONFIG_DEFAULT_VALUE_SWEEP_HIDDEN_EXCLUSIONS),
+            ConfigKey::SweepOneFileSystem    => Some(CONFIG_DEFAULT_VALUE_SWEEP_ONE_FILE_SYSTEM),
+            ConfigKey::SweepEmptyDirs        => Some(CONFIG_DEFAULT_VALUE_SWEEP_EMPTY_DIRS),
+            ConfigKey::SweepEmptyDirsMinAge  => Some(CONFIG_DEFAULT_VALUE_SWEEP_EMPTY_DIR_AGE),
+            ConfigKey::SweepKeepMarker       => Some(CONFIG_DEFAULT_VALUE_SWEEP_KEEP_MARKER),
+            ConfigKey::SweepMinSize          |
+            ConfigKey::SweepMaxSize          |
+            ConfigKey::SweepMaxTotalBytes    => Some(CONFIG_DEFAULT_VALUE_SWEEP_SIZE_LIMITS),
+            ConfigKey::SweepBuckets          => Some(CONFIG_DEFAULT_VALUE_SWEEP_BUCKETS),
+            ConfigKey::RulesKeepLargerThan   |
+            ConfigKey::RulesKeepExtensions   |
+            ConfigKey::RulesKeepOwners       |
+            ConfigKey::RulesKeepMode         |
+            ConfigKey::RulesSweepSmallerThan |
+            ConfigKey::RulesSweepExtensions  |
+            ConfigKey::RulesSweepOwners      |
+            ConfigKey::RulesSweepMode        => Some(CONFIG_DEFAULT_VALUE_RULES),
+            _                                => None,
         }
     }
 
@@ -169,20 +302,19 @@ impl Config {
 
         print_with_tag(Tag::Info, format!("Get the parameter for \"{}\"", key.to_str()));
 
-        let result = self.toml
-            .lookup(key.to_str())
-            .ok_or(ConfigError::new(match *key {
+        let value = match (self.toml.lookup(key.to_str()), key.default_value()) {
+            (Some(value), _            ) => try!(value.as_str().ok_or(ConfigError::new(ConfigErrorKind::NonStringValue))),
+            (None       , Some(default)) => default,
+            (None       , None         ) => return Err(ConfigError::new(match *key {
                 ConfigKey::BurnMoratorium  => ConfigErrorKind::NotFoundBurnMoratorium,
                 ConfigKey::SweepMoratorium => ConfigErrorKind::NotFoundSweepMoratorium,
                 ConfigKey::SweepPeriod     => ConfigErrorKind::NotFoundSweepPeriod,
                 ConfigKey::SweepTime       => ConfigErrorKind::NotFoundSweepTime,
-            }));
-        let value = try!(result);
+                _                          => unreachable!("Optional key must have default value!!"),
+            })),
+        };
 
-        value
-            .as_str()
-            .ok_or(ConfigError::new(ConfigErrorKind::NonStringValue))
-            .and_then(|s| Self::validate(key, s))
+        Self::validate(key, value)
     }
 
     pub fn to_duration(value: String) -> Duration {
@@ -205,6 +337,68 @@ impl Config {
         }
     }
 
+    pub fn to_bool(value: String) -> bool {
+        match value.as_ref() {
+            "true"  => true,
+            "false" => false,
+            _       => unreachable!("Wrong to use this function!!"),
+        }
+    }
+
+    pub fn to_bytes(value: String) -> u64 {
+        let mut value   = value.split(' ');
+        let (num, unit) = match (value.next(), value.next()) {
+            (Some(num), Some(unit)) => (num, unit),
+            _                       => unreachable!("Wrong to use this function!!"),
+        };
+        let num = match num.parse::<u64>() {
+            Ok(u) => u,
+            _     => unreachable!("Wrong to use this function!!"),
+        };
+
+        match SIZE_UNITS.iter().find(|&&(u, _)| u == unit) {
+            Some(&(_, bytes)) => num.saturating_mul(bytes),
+            None              => unreachable!("Wrong to use this function!!"),
+        }
+    }
+
+    pub fn to_list(value: String) -> Vec<String> {
+        value
+            .split(',')
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<String>>()
+    }
+
+    // "images: png, image/*; archives: zip" means bucket names with extensions and MIME types.
+    pub fn to_buckets(value: String) -> Vec<(String, Vec<String>)> {
+        value
+            .split(';')
+            .filter(|b| !b.trim().is_empty())
+            .map(|b| match b.find(':') {
+                Some(i) => (b[..i].trim().to_string(), Self::to_list(b[i + 1..].to_string())),
+                None    => (b.trim().to_string(), Vec::new()),
+            })
+            .collect::<Vec<(String, Vec<String>)>>()
+    }
+
+    pub fn to_uids(value: String) -> Vec<u32> {
+        Self::to_list(value)
+            .into_iter()
+            .map(|v| match v.parse::<u32>() {
+                Ok(u) => u,
+                _     => unreachable!("Wrong to use this function!!"),
+            })
+            .collect::<Vec<u32>>()
+    }
+
+    pub fn to_mode(value: String) -> u32 {
+        match u32::from_str_radix(&value, 8) {
+            Ok(u) => u,
+            _     => unreachable!("Wrong to use this function!!"),
+        }
+    }
+
     pub fn to_hour_and_minute(value: String) -> (u32, u32) {
         let time = match Self::to_naive_time(value) {
             Ok(t)  => t,
@@ -244,6 +438,103 @@ impl Config {
         }
     }
 
+    fn capture_size(value: &str) -> Option<String> {
+        let re = match Regex::new(r"^(?P<num>\d+)\s?(?P<unit>[A-Za-z]*)$") {
+            Ok(re) => re,
+            Err(_) => unreachable!("Wrong to use this function!!"),
+        };
+        let caps = match re.captures(value) {
+            Some(caps) => caps,
+            None       => return None,
+        };
+        let (num, unit) = match (caps.name("num"), caps.name("unit")) {
+            (Some(num), Some("")  ) => (num, "B"),
+            (Some(num), Some(unit)) => (num, unit),
+            _                       => return None,
+        };
+        if num.parse::<u64>().is_err() {
+            return None;
+        }
+
+        SIZE_UNITS
+            .iter()
+            .find(|&&(u, _)| u.to_lowercase() == unit.to_lowercase())
+            .map(|&(u, _)| format!("{} {}", num, u))
+    }
+
+    fn validate_extensions(value: &str) -> Option<String> {
+        let extensions = Self::to_list(value.to_string())
+            .into_iter()
+            .map(|e| e.trim_left_matches('.').to_string())
+            .collect::<Vec<String>>();
+        if extensions.is_empty() || extensions.iter().any(|e| e.is_empty() || e.contains('/') || e.contains(char::is_whitespace)) {
+            return None;
+        }
+        Some(extensions.join(", "))
+    }
+
+    fn validate_buckets(value: &str) -> Option<String> {
+        let re = match Regex::new(r"^[a-z]+/([a-z0-9.+-]+|\*)$") {
+            Ok(re) => re,
+            Err(_) => unreachable!("Wrong to use this function!!"),
+        };
+
+        let mut buckets = Vec::new();
+        for (name, items) in Self::to_buckets(value.to_string()) {
+            if Self::validate_file_name(&name).is_none() || items.is_empty() {
+                return None;
+            }
+            // Items with "/" are MIME types, and the others are extensions.
+            let mut sorts = Vec::new();
+            for item in items {
+                let item = item.trim_left_matches('.').to_string();
+                let item = if item.contains('/') { item.to_lowercase() } else { item };
+                if (item.contains('/') && !re.is_match(&item)) || item.is_empty() || item.contains(char::is_whitespace) {
+                    return None;
+                }
+                sorts.push(item);
+            }
+            buckets.push(format!("{}: {}", name, sorts.join(", ")));
+        }
+        if buckets.is_empty() {
+            return None;
+        }
+        Some(buckets.join("; "))
+    }
+
+    fn validate_hidden_names(value: &str) -> Option<String> {
+        let names = Self::to_list(value.to_string());
+        if names.iter().any(|n| !n.starts_with('.') || n == "." || n == ".." || n.contains('/') || n.contains(char::is_whitespace)) {
+            return None;
+        }
+        Some(names.join(", "))
+    }
+
+    fn validate_file_name(value: &str) -> Option<String> {
+        if value.is_empty() || value == "." || value == ".." || value.contains('/') || value.contains(char::is_whitespace) {
+            return None;
+        }
+        Some(value.to_string())
+    }
+
+    fn validate_uids(value: &str) -> Option<String> {
+        let uids = Self::to_list(value.to_string());
+        if uids.is_empty() || uids.iter().any(|u| u.parse::<u32>().is_err()) {
+            return None;
+        }
+        Some(uids.join(", "))
+    }
+
+    fn validate_mode(value: &str) -> Option<String> {
+        if value.is_empty() || value.len() > 4 {
+            return None;
+        }
+        match u32::from_str_radix(value, 8) {
+            Ok(mode) if mode != 0 => Some(format!("{:04o}", mode)),
+            _                     => None,
+        }
+    }
+
     fn validate<CK: Borrow<ConfigKey>, S: AsRef<str>>(key: CK, value: S) -> Result<String, ConfigError> {
         let value = value.as_ref().trim();
 
@@ -274,6 +565,62 @@ impl Config {
                     Err(_) => Err(ConfigError::new(ConfigErrorKind::SweepTime)),
                 }
             },
+            ConfigKey::SweepKeepGitTracked => {
+                match value {
+                    "true" | "false" => Ok(value.to_string()),
+                    _                => Err(ConfigError::new(ConfigErrorKind::SweepKeepGitTracked)),
+                }
+            },
+            ConfigKey::SweepTimestamp => {
+                match value {
+                    "atime" | "mtime" | "ctime" | "newest" => Ok(value.to_string()),
+                    _                                      => Err(ConfigError::new(ConfigErrorKind::SweepTimestamp)),
+                }
+            },
+            ConfigKey::SweepIncludeHidden => {
+                match value {
+                    "true" | "false" => Ok(value.to_string()),
+                    _                => Err(ConfigError::new(ConfigErrorKind::SweepIncludeHidden)),
+                }
+            },
+            ConfigKey::SweepHiddenExclusions => {
+                Self::validate_hidden_names(value).ok_or(ConfigError::new(ConfigErrorKind::SweepHiddenExclusions))
+            },
+            ConfigKey::SweepOneFileSystem => {
+                match value {
+                    "true" | "false" => Ok(value.to_string()),
+                    _                => Err(ConfigError::new(ConfigErrorKind::SweepOneFileSystem)),
+                }
+            },
+            ConfigKey::SweepEmptyDirs => {
+                match value {
+                    "true" | "false" => Ok(value.to_string()),
+                    _                => Err(ConfigError::new(ConfigErrorKind::SweepEmptyDirs)),
+                }
+            },
+            ConfigKey::SweepEmptyDirsMinAge => {
+                let (num, unit) = match Self::capture_moratorium(value, "minutes?|hours?|days?|weeks?") {
+                    (Some(num), Some(unit)) => (num, unit),
+                    _                       => return Err(ConfigError::new(ConfigErrorKind::SweepEmptyDirsMinAge))
+                };
+                Ok(format!("{} {}", num, unit))
+            },
+            ConfigKey::SweepKeepMarker => {
+                Self::validate_file_name(value).ok_or(ConfigError::new(ConfigErrorKind::SweepKeepMarker))
+            },
+            _ if value.is_empty() => Ok(value.to_string()),
+            ConfigKey::SweepMinSize          => Self::capture_size(value)       .ok_or(ConfigError::new(ConfigErrorKind::SweepMinSize)),
+            ConfigKey::SweepMaxSize          => Self::capture_size(value)       .ok_or(ConfigError::new(ConfigErrorKind::SweepMaxSize)),
+            ConfigKey::SweepMaxTotalBytes    => Self::capture_size(value)       .ok_or(ConfigError::new(ConfigErrorKind::SweepMaxTotalBytes)),
+            ConfigKey::SweepBuckets          => Self::validate_buckets(value)   .ok_or(ConfigError::new(ConfigErrorKind::SweepBuckets)),
+            ConfigKey::RulesKeepLargerThan   => Self::capture_size(value)       .ok_or(ConfigError::new(ConfigErrorKind::RulesKeepLargerThan)),
+            ConfigKey::RulesKeepExtensions   => Self::validate_extensions(value).ok_or(ConfigError::new(ConfigErrorKind::RulesKeepExtensions)),
+            ConfigKey::RulesKeepOwners       => Self::validate_uids(value)      .ok_or(ConfigError::new(ConfigErrorKind::RulesKeepOwners)),
+            ConfigKey::RulesKeepMode         => Self::validate_mode(value)      .ok_or(ConfigError::new(ConfigErrorKind::RulesKeepMode)),
+            ConfigKey::RulesSweepSmallerThan => Self::capture_size(value)       .ok_or(ConfigError::new(ConfigErrorKind::RulesSweepSmallerThan)),
+            ConfigKey::RulesSweepExtensions  => Self::validate_extensions(value).ok_or(ConfigError::new(ConfigErrorKind::RulesSweepExtensions)),
+            ConfigKey::RulesSweepOwners      => Self::validate_uids(value)      .ok_or(ConfigError::new(ConfigErrorKind::RulesSweepOwners)),
+            ConfigKey::RulesSweepMode        => Self::validate_mode(value)      .ok_or(ConfigError::new(ConfigErrorKind::RulesSweepMode)),
         }
     }
 }
@@ -282,10 +629,30 @@ impl Config {
 #[test]
 fn config_key_to_pair_should_return_pair() {
     let keys = [
-        (ConfigKey::BurnMoratorium , CONFIG_KEY_BURN_MORATORIUM ),
-        (ConfigKey::SweepMoratorium, CONFIG_KEY_SWEEP_MORATORIUM),
-        (ConfigKey::SweepPeriod    , CONFIG_KEY_SWEEP_PERIOD    ),
-        (ConfigKey::SweepTime      , CONFIG_KEY_SWEEP_TIME      ),
+        (ConfigKey::BurnMoratorium       , CONFIG_KEY_BURN_MORATORIUM         ),
+        (ConfigKey::SweepMoratorium      , CONFIG_KEY_SWEEP_MORATORIUM        ),
+        (ConfigKey::SweepPeriod          , CONFIG_KEY_SWEEP_PERIOD            ),
+        (ConfigKey::SweepTime            , CONFIG_KEY_SWEEP_TIME              ),
+        (ConfigKey::SweepKeepGitTracked  , CONFIG_KEY_SWEEP_KEEP_GIT_TRACKED  ),
+        (ConfigKey::SweepTimestamp       , CONFIG_KEY_SWEEP_TIMESTAMP         ),
+        (ConfigKey::SweepIncludeHidden   , CONFIG_KEY_SWEEP_INCLUDE_HIDDEN    ),
+        (ConfigKey::SweepHiddenExclusions, CONFIG_KEY_SWEEP_HIDDEN_EXCLUSIONS ),
+        (ConfigKey::SweepOneFileSystem   , CONFIG_KEY_SWEEP_ONE_FILE_SYSTEM   ),
+        (ConfigKey::SweepEmptyDirs       , CONFIG_KEY_SWEEP_EMPTY_DIRS        ),
+        (ConfigKey::SweepEmptyDirsMinAge , CONFIG_KEY_SWEEP_EMPTY_DIRS_MIN_AGE),
+        (ConfigKey::SweepKeepMarker      , CONFIG_KEY_SWEEP_KEEP_MARKER       ),
+        (ConfigKey::SweepMinSize         , CONFIG_KEY_SWEEP_MIN_SIZE          ),
+        (ConfigKey::SweepMaxSize         , CONFIG_KEY_SWEEP_MAX_SIZE          ),
+        (ConfigKey::SweepMaxTotalBytes   , CONFIG_KEY_SWEEP_MAX_TOTAL_BYTES   ),
+        (ConfigKey::SweepBuckets         , CONFIG_KEY_SWEEP_BUCKETS           ),
+        (ConfigKey::RulesKeepLargerThan  , CONFIG_KEY_RULES_KEEP_LARGER_THAN  ),
+        (ConfigKey::RulesKeepExtensions  , CONFIG_KEY_RULES_KEEP_EXTENSIONS   ),
+        (ConfigKey::RulesKeepOwners      , CONFIG_KEY_RULES_KEEP_OWNERS       ),
+        (ConfigKey::RulesKeepMode        , CONFIG_KEY_RULES_KEEP_MODE         ),
+        (ConfigKey::RulesSweepSmallerThan, CONFIG_KEY_RULES_SWEEP_SMALLER_THAN),
+        (ConfigKey::RulesSweepExtensions , CONFIG_KEY_RULES_SWEEP_EXTENSIONS  ),
+        (ConfigKey::RulesSweepOwners     , CONFIG_KEY_RULES_SWEEP_OWNERS      ),
+        (ConfigKey::RulesSweepMode       , CONFIG_KEY_RULES_SWEEP_MODE        ),
     ];
     for &(ref key, ref correct) in &keys {
         let (first, second) = key.to_pair();
@@ -404,6 +771,15 @@ mod tests {
         assert!(config.get(ConfigKey::SweepTime      ).is_ok());
     }
 
+    #[test]
+    fn get_should_return_default_value_for_optional_key() {
+        let config = Config::default();
+
+        assert_eq!(CONFIG_DEFAULT_VALUE_SWEEP_KEEP_GIT_TRACKED.to_string(), config.get(ConfigKey::SweepKeepGitTracked).unwrap());
+        assert_eq!(CONFIG_DEFAULT_VALUE_SWEEP_SIZE_LIMITS     .to_string(), config.get(ConfigKey::SweepMaxTotalBytes ).unwrap());
+        assert_eq!(CONFIG_DEFAULT_VALUE_RULES                 .to_string(), config.get(ConfigKey::RulesKeepLargerThan).unwrap());
+    }
+
     #[test]
     fn set_should_replace_value_with_new_value() {
         let raw_values = vec![
@@ -505,6 +881,48 @@ mod tests {
                 .unwrap();
             assert_eq!(raw_value.to_string(), config.get(ConfigKey::SweepTime).unwrap())
         }
+
+        let raw_values = ["true", "false"];
+        for raw_value in &raw_values {
+            let config = Config::default()
+                .set(ConfigKey::SweepKeepGitTracked, &raw_value)
+                .unwrap();
+            assert_eq!(raw_value.to_string(), config.get(ConfigKey::SweepKeepGitTracked).unwrap())
+        }
+
+        let data_set = vec![
+            (ConfigKey::SweepTimestamp       , "mtime"           , "mtime"           ),
+            (ConfigKey::SweepTimestamp       , "newest"          , "newest"          ),
+            (ConfigKey::SweepIncludeHidden   , "true"            , "true"            ),
+            (ConfigKey::SweepHiddenExclusions, ".git,.DS_Store"  , ".git, .DS_Store" ),
+            (ConfigKey::SweepHiddenExclusions, ""                , ""                ),
+            (ConfigKey::SweepOneFileSystem   , "true"            , "true"            ),
+            (ConfigKey::SweepEmptyDirs       , "false"           , "false"           ),
+            (ConfigKey::SweepEmptyDirsMinAge , "3days"           , "3 days"          ),
+            (ConfigKey::SweepKeepMarker      , "KEEP"            , "KEEP"            ),
+            (ConfigKey::SweepMinSize         , "1mib"            , "1 MiB"           ),
+            (ConfigKey::SweepMaxSize         , "10 GB"           , "10 GB"           ),
+            (ConfigKey::SweepMaxTotalBytes   , "1073741824"      , "1073741824 B"    ),
+            (ConfigKey::SweepMaxTotalBytes   , ""                , ""                ),
+            (ConfigKey::SweepBuckets         , "a:.png;b:X/*"    , "a: png; b: x/*"  ),
+            (ConfigKey::SweepBuckets         , ""                , ""                ),
+            (ConfigKey::RulesKeepLargerThan  , "1GiB"            , "1 GiB"           ),
+            (ConfigKey::RulesKeepLargerThan  , "500 mb"          , "500 MB"          ),
+            (ConfigKey::RulesKeepLargerThan  , ""                , ""                ),
+            (ConfigKey::RulesSweepSmallerThan, "1"               , "1 B"             ),
+            (ConfigKey::RulesKeepExtensions  , ".iso,tar.gz"     , "iso, tar.gz"     ),
+            (ConfigKey::RulesSweepExtensions , "part, crdownload", "part, crdownload"),
+            (ConfigKey::RulesKeepOwners      , "0,1000"          , "0, 1000"         ),
+            (ConfigKey::RulesSweepOwners     , "1000"            , "1000"            ),
+            (ConfigKey::RulesKeepMode        , "111"             , "0111"            ),
+            (ConfigKey::RulesSweepMode       , "0002"            , "0002"            ),
+        ];
+        for (key, input, correct) in data_set.into_iter() {
+            let config = Config::default()
+                .set(&key, input)
+                .unwrap();
+            assert_eq!(correct.to_string(), config.get(&key).unwrap())
+        }
     }
     #[test]
     fn set_should_return_err() {
@@ -558,6 +976,37 @@ mod tests {
             (ConfigKey::SweepTime,  "24:00"   , ConfigError::new(ConfigErrorKind::SweepTime)),
             (ConfigKey::SweepTime,  "00"      , ConfigError::new(ConfigErrorKind::SweepTime)),
             (ConfigKey::SweepTime,  "00:00:00", ConfigError::new(ConfigErrorKind::SweepTime)),
+
+            (ConfigKey::SweepKeepGitTracked, "yes", ConfigError::new(ConfigErrorKind::SweepKeepGitTracked)),
+            (ConfigKey::SweepKeepGitTracked, "1"  , ConfigError::new(ConfigErrorKind::SweepKeepGitTracked)),
+
+            (ConfigKey::SweepTimestamp       , "btime" , ConfigError::new(ConfigErrorKind::SweepTimestamp)),
+            (ConfigKey::SweepTimestamp       , "oldest", ConfigError::new(ConfigErrorKind::SweepTimestamp)),
+            (ConfigKey::SweepIncludeHidden   , "yes"   , ConfigError::new(ConfigErrorKind::SweepIncludeHidden)),
+            (ConfigKey::SweepHiddenExclusions, "git"   , ConfigError::new(ConfigErrorKind::SweepHiddenExclusions)),
+            (ConfigKey::SweepHiddenExclusions, ".a/.b" , ConfigError::new(ConfigErrorKind::SweepHiddenExclusions)),
+            (ConfigKey::SweepOneFileSystem   , "yes"   , ConfigError::new(ConfigErrorKind::SweepOneFileSystem)),
+            (ConfigKey::SweepEmptyDirs       , "no"    , ConfigError::new(ConfigErrorKind::SweepEmptyDirs)),
+            (ConfigKey::SweepEmptyDirsMinAge , "1 year", ConfigError::new(ConfigErrorKind::SweepEmptyDirsMinAge)),
+            (ConfigKey::SweepKeepMarker      , "a/b"   , ConfigError::new(ConfigErrorKind::SweepKeepMarker)),
+            (ConfigKey::SweepKeepMarker      , ""      , ConfigError::new(ConfigErrorKind::SweepKeepMarker)),
+            (ConfigKey::SweepMinSize         , "1 KiBs", ConfigError::new(ConfigErrorKind::SweepMinSize)),
+            (ConfigKey::SweepMaxSize         , "-1 GB" , ConfigError::new(ConfigErrorKind::SweepMaxSize)),
+            (ConfigKey::SweepMaxTotalBytes   , "1.5 TB", ConfigError::new(ConfigErrorKind::SweepMaxTotalBytes)),
+            (ConfigKey::SweepBuckets         , "images", ConfigError::new(ConfigErrorKind::SweepBuckets)),
+            (ConfigKey::SweepBuckets         , "a/b: x", ConfigError::new(ConfigErrorKind::SweepBuckets)),
+            (ConfigKey::SweepBuckets         , "a: b/" , ConfigError::new(ConfigErrorKind::SweepBuckets)),
+            (ConfigKey::RulesKeepLargerThan  , "-1 GiB", ConfigError::new(ConfigErrorKind::RulesKeepLargerThan)),
+            (ConfigKey::RulesKeepLargerThan  , "1 PiB" , ConfigError::new(ConfigErrorKind::RulesKeepLargerThan)),
+            (ConfigKey::RulesKeepLargerThan  , "GiB"   , ConfigError::new(ConfigErrorKind::RulesKeepLargerThan)),
+            (ConfigKey::RulesSweepSmallerThan, "1.5 KB", ConfigError::new(ConfigErrorKind::RulesSweepSmallerThan)),
+            (ConfigKey::RulesKeepExtensions  , ","     , ConfigError::new(ConfigErrorKind::RulesKeepExtensions)),
+            (ConfigKey::RulesSweepExtensions , "a/b"   , ConfigError::new(ConfigErrorKind::RulesSweepExtensions)),
+            (ConfigKey::RulesKeepOwners      , "root"  , ConfigError::new(ConfigErrorKind::RulesKeepOwners)),
+            (ConfigKey::RulesSweepOwners     , "-1"    , ConfigError::new(ConfigErrorKind::RulesSweepOwners)),
+            (ConfigKey::RulesKeepMode        , "0"     , ConfigError::new(ConfigErrorKind::RulesKeepMode)),
+            (ConfigKey::RulesSweepMode       , "8"     , ConfigError::new(ConfigErrorKind::RulesSweepMode)),
+            (ConfigKey::RulesSweepMode       , "10000" , ConfigError::new(ConfigErrorKind::RulesSweepMode)),
         ];
 
         for (key, value, correct) in data_set.into_iter() {
@@ -615,6 +1064,26 @@ mod tests {
         Config::to_duration("invalid value".to_string());
     }
 
+    #[test]
+    fn to_bytes_should_return_bytes() {
+        let data_set = vec![
+            ("0 B"    , 0),
+            ("1 KB"   , 1000),
+            ("1 KiB"  , 1024),
+            ("2 MiB"  , 2 * 1024 * 1024),
+            ("1 GiB"  , 1024 * 1024 * 1024),
+            ("1 TB"   , 1000 * 1000 * 1000 * 1000),
+        ];
+        for (input, correct) in data_set.into_iter() {
+            assert_eq!(correct, Config::to_bytes(input.to_string()));
+        }
+    }
+    #[test]
+    #[should_panic(expected = "entered unreachable code")]
+    fn to_bytes_should_panic_for_incoherent() {
+        Config::to_bytes("invalid value".to_string());
+    }
+
     #[test]
     fn to_hour_and_minute_should_return_tuple() {
         let data_set = vec![
@@ -1,4 +1,9 @@
-use constant::{IGNORE_FILE_NAME, MAIN_DIR};
+extern crate regex;
+
+use self::regex::{Error as RegexError, Regex};
+
+use constant::{IGNORE_FILE_NAME, LOCAL_IGNORE_FILE_NAME, MAIN_DIR};
+use error::{CliError, IgnoreError};
 use lib::io::*;
 use lib::fs::*;
 use std::collections::BTreeSet;
@@ -7,9 +12,298 @@ use std::io::{Error as IoError, Read};
 use std::path::{Path, PathBuf};
 
 
+fn is_pattern<S: AsRef<str>>(entry: S) -> bool {
+    entry.as_ref().contains(|c| c == '*' || c == '?' || c == '[')
+}
+
+fn glob_to_regex<S: AsRef<str>>(glob: S) -> Result<Regex, RegexError> {
+    let mut re    = "^".to_string();
+    let mut chars = glob.as_ref().chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => if chars.peek() == Some(&'*') {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    re.push_str("(?:.*/)?");
+                } else {
+                    re.push_str(".*");
+                }
+            } else {
+                re.push_str("[^/]*");
+            },
+            '?' => re.push_str("[^/]"),
+            '[' => {
+                let negated    = chars.peek() == Some(&'!');
+                let mut class  = String::new();
+                let mut closed = false;
+                if negated {
+                    chars.next();
+                }
+                while let Some(c) = chars.next() {
+                    match c {
+                        ']' => { closed = true; break },
+                        '-' => class.push('-'),
+                        _   => class.push_str(&regex::quote(&c.to_string())),
+                    }
+                }
+                if closed {
+                    re.push_str(if negated { "[^" } else { "[" });
+                    re.push_str(&class);
+                    re.push(']');
+                } else {
+                    // Unclosed "[" is not a character class but a character.
+                    re.push_str(&regex::quote(if negated { "[!" } else { "[" }));
+                    re.push_str(&class);
+                }
+            },
+            _ => re.push_str(&regex::quote(&c.to_string())),
+        }
+    }
+    re.push('$');
+
+    Regex::new(&re)
+}
+
+
+const PIN_PREFIX     : &'static str = "+";
+const NEGATION_PREFIX: &'static str = "!";
+const REGEX_PREFIX   : &'static str = "re:";
+
+fn is_regex<S: AsRef<str>>(body: S) -> bool {
+    body.as_ref().starts_with(REGEX_PREFIX)
+}
+
+fn split_prefix<'a>(entry: &'a str, prefix: &str) -> (bool, &'a str) {
+    if entry.starts_with(prefix) {
+        (true, &entry[prefix.len()..])
+    } else {
+        (false, entry)
+    }
+}
+
+// Pinned entries (e.g. "+./file") are never removed even if their targets do not exist.
+fn split_pin<S: AsRef<str>>(entry: &S) -> (bool, &str) {
+    split_prefix(entry.as_ref(), PIN_PREFIX)
+}
+
+fn split_negation<S: AsRef<str>>(entry: &S) -> (bool, &str) {
+    split_prefix(entry.as_ref(), NEGATION_PREFIX)
+}
+
+fn normalize<S: AsRef<str>>(entry: S, base: &str) -> String {
+    let (pinned, entry) = split_pin(&entry);
+    let (negated, body) = split_negation(&entry);
+    let body            = if is_regex(body) {
+        body.to_string()
+    } else {
+        trim_end_separator(supply_current_dir_prefix(body))
+    };
+    let body            = if base != MAIN_DIR && body.starts_with("./") {
+        format!("{}{}", base, &body[1..])
+    } else {
+        body
+    };
+    let body            = if negated { format!("{}{}", NEGATION_PREFIX, body) } else { body };
+    if pinned { format!("{}{}", PIN_PREFIX, body) } else { body }
+}
+
+fn is_alive<S: AsRef<str>>(entry: S) -> bool {
+    let (pinned, entry) = split_pin(&entry);
+    let (_, body)       = split_negation(&entry);
+    pinned || is_regex(body) || is_pattern(body) || Path::new(body).exists()
+}
+
+
+#[derive(Debug)]
+enum Matcher {
+    Path(String),
+    Glob(Regex),
+    // Regular expressions are matched against paths relative to the directory which has the rule.
+    Regex(Regex, String),
+}
+
+impl Matcher {
+    // A pattern which matches a directory also matches everything in it.
+    fn is_match_with_ancestors(re: &Regex, path: &str) -> bool {
+        let mut target = path;
+        loop {
+            if re.is_match(target) {
+                return true;
+            }
+            match target.rfind('/') {
+                Some(i) => target = &target[..i],
+                None    => return false,
+            }
+        }
+    }
+
+    fn is_match(&self, path: &str) -> bool {
+        match *self {
+            Matcher::Path(ref p)  => path == p || path.starts_with(&format!("{}/", p)),
+            Matcher::Glob(ref re) => Self::is_match_with_ancestors(re, path),
+            Matcher::Regex(ref re, ref base) => {
+                if base == MAIN_DIR {
+                    Self::is_match_with_ancestors(re, path)
+                } else if path.starts_with(&format!("{}/", base)) {
+                    Self::is_match_with_ancestors(re, &format!(".{}", &path[base.len()..]))
+                } else {
+                    false
+                }
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    entry  : String,
+    negated: bool,
+    matcher: Matcher,
+    origin : String,
+}
+
+impl Rule {
+    pub fn entry(&self) -> &str {
+        &self.entry
+    }
+
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+}
+
+#[derive(Debug)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    // Local ignore files are looked for only in "roots" and directories on the way to them,
+    // since the others never affect paths in "roots".
+    pub fn read<S: AsRef<str>>(hidden: &HiddenPolicy, boundary: &Boundary, roots: &[S]) -> Result<Self, CliError> {
+        let mut rules = try!(try!(Ignore::read()).to_rules());
+
+        let mut paths = BTreeSet::new();
+        for root in roots.iter().map(|r| Path::new(r.as_ref())) {
+            if hidden.is_skipped_path(root) || boundary.is_beyond(root) {
+                continue;
+            }
+            let on_the_way = root
+                .ancestors()
+                .skip(1)
+                .take_while(|a| *a != Path::new(""))
+                .map(|a| a.join(LOCAL_IGNORE_FILE_NAME))
+                .filter(|p| p.is_file());
+            paths.extend(on_the_way);
+
+            if root.is_dir() {
+                paths.extend(find_files_by_name(root, LOCAL_IGNORE_FILE_NAME, hidden, boundary));
+            }
+        }
+
+        for path in paths {
+            let local = try!(Ignore::read_local(path));
+            rules.rules.extend(try!(local.to_rules()).rules);
+        }
+
+        Ok(rules)
+    }
+
+    // Like ".gitignore", the last matching rule decides whether the path is ignored.
+    pub fn last_match<S: AsRef<str>>(&self, path: S) -> Option<&Rule> {
+        let path = path.as_ref();
+        self.rules
+            .iter()
+            .rev()
+            .find(|r| r.matcher.is_match(path))
+    }
+
+    // Whether a negated rule may match something in "dir", i.e. whether "dir" cannot be skipped as a whole.
+    // This is conservative: a regular expression may match anything.
+    pub fn may_unignore_in<S: AsRef<str>>(&self, dir: S) -> bool {
+        let dir = format!("{}/", dir.as_ref());
+        self.rules
+            .iter()
+            .filter(|r| r.negated)
+            .any(|r| {
+                if let Matcher::Regex(..) = r.matcher {
+                    return true;
+                }
+                let (_, body) = split_negation(&r.entry);
+                let prefix    = match body.find(|c| c == '*' || c == '?' || c == '[') {
+                    Some(i) => &body[..i],
+                    None    => body,
+                };
+                prefix.starts_with(&dir) || dir.starts_with(prefix)
+            })
+    }
+
+    // Rules given as arguments, e.g. for "restore".
+    pub fn from_arguments(arguments: &Vec<String>) -> Result<Self, IgnoreError> {
+        Ignore::from_contents("arguments", MAIN_DIR.to_string(), arguments.join("\n")).to_rules()
+    }
+
+    pub fn is_ignored<S: AsRef<str>>(&self, path: S) -> bool {
+        self.last_match(path).map_or(false, |r| !r.negated)
+    }
+}
+
+
+const COMMENT_PREFIX: &'static str = "#";
+
+#[derive(Debug, PartialEq)]
+enum Line {
+    Blank,
+    Comment(String),
+    Entry { raw: String, entry: String },
+}
+
+impl Line {
+    fn parse<S: AsRef<str>>(line: S, base: &str) -> Self {
+        let raw     = line.as_ref();
+        let trimmed = raw.trim();
+
+        if trimmed.is_empty() {
+            Line::Blank
+        } else if trimmed.starts_with(COMMENT_PREFIX) {
+            Line::Comment(raw.to_string())
+        } else {
+            Line::Entry { raw: raw.to_string(), entry: normalize(trimmed, base) }
+        }
+    }
+
+    fn from_entry(entry: String) -> Self {
+        Line::Entry { raw: entry.clone(), entry: entry }
+    }
+
+    fn to_str(&self) -> &str {
+        match *self {
+            Line::Blank                 => "",
+            Line::Comment(ref c)        => c,
+            Line::Entry { ref raw, .. } => raw,
+        }
+    }
+
+    fn entry(&self) -> Option<&String> {
+        match *self {
+            Line::Entry { ref entry, .. } => Some(entry),
+            _                             => None,
+        }
+    }
+}
+
+
 #[derive(Debug)]
 pub struct Ignore {
-    entries: BTreeSet<String>,
+    path : PathBuf,
+    base : String,
+    lines: Vec<Line>,
 }
 
 impl Ignore {
@@ -22,120 +316,362 @@ impl Ignore {
     }
 
 
-    fn to_string(entries: BTreeSet<String>) -> String {
-        entries
+    fn to_string(&self) -> String {
+        self.lines
             .iter()
-            .fold(String::new(), |contents, entry| contents + entry + "\n")
+            .fold(String::new(), |contents, line| contents + line.to_str() + "\n")
     }
 
     pub fn create(self) -> Result<(), IoError> {
         print_with_tag(Tag::Info, format!("Create \"{}\" file", IGNORE_FILE_NAME));
 
-        let (dirs, files) = self.dirs_and_files();
+        super::create_setting_file(&self.path, self.to_string())
+    }
 
-        let files = files
-            .into_iter()
-            .filter(|f| !dirs.iter().any(|d| f.starts_with(d)))
-            .collect::<BTreeSet<String>>();
 
-        super::create_setting_file(Self::path(), Self::to_string(dirs) + &Self::to_string(files))
+    fn _new(path: PathBuf, base: String, lines: Vec<Line>) -> Self {
+        Ignore { path: path, base: base, lines: lines }
     }
 
+    fn from_contents<P: AsRef<Path>, S: AsRef<str>>(path: P, base: String, contents: S) -> Self {
+        let lines = contents
+            .as_ref()
+            .lines()
+            .map(|l| Line::parse(l, &base))
+            .collect::<Vec<Line>>();
 
-    fn _new(entries: BTreeSet<String>) -> Self {
-        Ignore { entries: entries }
+        Self::_new(path.as_ref().to_path_buf(), base, lines)
     }
 
     pub fn new() -> Self {
-        Self::_new(BTreeSet::new())
+        Self::_new(Self::path(), MAIN_DIR.to_string(), Vec::new())
     }
 
-    pub fn default() -> Self {
-        let current_entries = walk_dir(MAIN_DIR);
+    pub fn default(hidden: &HiddenPolicy) -> Self {
+        let current_entries = walk_dir(MAIN_DIR, hidden)
+            .into_iter()
+            .map(Line::from_entry)
+            .collect::<Vec<Line>>();
 
-        Self::_new(current_entries)
+        Self::_new(Self::path(), MAIN_DIR.to_string(), current_entries)
     }
 
-    pub fn read() -> Result<Self, IoError> {
-        print_with_tag(Tag::Info, format!("Read \"{}\" file", IGNORE_FILE_NAME));
-
-        let mut f = try!(File::open(Self::path()));
+    fn read_from<P: AsRef<Path>>(path: P, base: String) -> Result<Self, CliError> {
+        let mut f = try!(File::open(&path));
 
         let mut contents = String::new();
         try!(f.read_to_string(&mut contents));
 
-        let entries = contents
-            .lines()
-            .map(|l| l.trim())
-            .filter(|s| Path::new(s).exists())
-            .map(|p| p.to_string())
-            .collect::<BTreeSet<String>>();
+        Ok(Self::from_contents(path, base, contents))
+    }
 
-        Ok(Self::_new(entries))
+    pub fn read() -> Result<Self, CliError> {
+        print_with_tag(Tag::Info, format!("Read \"{}\" file", IGNORE_FILE_NAME));
+
+        Self::read_from(Self::path(), MAIN_DIR.to_string())
     }
 
+    fn read_local<P: AsRef<Path>>(path: P) -> Result<Self, CliError> {
+        let path = path.as_ref();
 
-    pub fn dirs_and_files(self) -> (BTreeSet<String>, BTreeSet<String>) {
-        self.entries
-            .into_iter()
-            .partition(|s| Path::new(s).is_dir())
+        print_with_tag(Tag::Info, format!("Read \"{}\" file", path.display()));
+
+        let base = match path.parent().and_then(|p| p.to_str()) {
+            Some(b) => b.to_string(),
+            None    => unreachable!("Cannot get base name from path!!"),
+        };
+
+        Self::read_from(path, base)
+    }
+
+
+    fn entries(&self) -> Vec<&String> {
+        self.lines
+            .iter()
+            .filter_map(Line::entry)
+            .collect::<Vec<&String>>()
+    }
+
+    pub fn to_rules(&self) -> Result<IgnoreRules, IgnoreError> {
+        let mut rules = Vec::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            let entry = match line.entry() {
+                Some(e) => e,
+                None    => continue,
+            };
+            let origin = format!("\"{}\" line {}", self.path.display(), i + 1);
+
+            let (_, entry)      = split_pin(entry);
+            let (negated, body) = split_negation(&entry);
+            let result          = if is_regex(body) {
+                Regex::new(&body[REGEX_PREFIX.len()..]).map(|re| Matcher::Regex(re, self.base.clone()))
+            } else if is_pattern(body) {
+                glob_to_regex(body).map(Matcher::Glob)
+            } else {
+                Ok(Matcher::Path(body.to_string()))
+            };
+            let matcher = try!(result.map_err(|e| IgnoreError::new(entry.to_string(), origin.clone(), e)));
+
+            rules.push(Rule { entry: entry.to_string(), negated: negated, matcher: matcher, origin: origin });
+        }
+
+        Ok(IgnoreRules { rules: rules })
     }
 
 
     pub fn add(mut self, paths: &Vec<String>) -> Self {
-        let paths_to_be_added = paths
+        let entries_to_be_added = paths
             .iter()
-            .filter(|p| Path::new(p).exists())
-            .map(supply_current_dir_prefix)
-            .map(trim_end_separator)
-            .collect::<BTreeSet<String>>();
+            .map(|p| normalize(p, &self.base))
+            .filter(|e| is_alive(e))
+            .collect::<Vec<String>>();
 
-        self.entries = self.entries
-            .union(&paths_to_be_added)
-            .cloned()
-            .collect::<BTreeSet<String>>();
+        for entry in entries_to_be_added {
+            if !self.entries().contains(&&entry) {
+                self.lines.push(Line::from_entry(entry));
+            }
+        }
 
         self
     }
 
+    // Both pinned and non-pinned entries are removed.
     pub fn remove(mut self, paths: &Vec<String>) -> Self {
-        let paths_to_be_removed = paths
+        let entries_to_be_removed = paths
             .iter()
-            .map(supply_current_dir_prefix)
-            .map(trim_end_separator)
+            .map(|p| normalize(p, &self.base))
+            .map(|e| split_pin(&e).1.to_string())
             .collect::<BTreeSet<String>>();
 
-        self.entries = self.entries
-            .difference(&paths_to_be_removed)
-            .cloned()
-            .collect::<BTreeSet<String>>();
+        self.lines.retain(|l| l.entry().map_or(true, |e| !entries_to_be_removed.contains(split_pin(e).1)));
+
+        self
+    }
+
+    pub fn refresh(mut self) -> Self {
+        let (alive, dead): (Vec<Line>, Vec<Line>) = self.lines
+            .into_iter()
+            .partition(|l| l.entry().map_or(true, is_alive));
+
+        for entry in dead.iter().filter_map(Line::entry) {
+            print_with_tag(Tag::Notice, format!("Remove \"{}\" from \"{}\" because it does not exist", entry, IGNORE_FILE_NAME));
+        }
 
+        self.lines = alive;
         self
     }
 }
 
 
+#[test]
+fn glob_to_regex_should_match() {
+    let data_set = [
+        ("./*.md"      , "./README.md"          ),
+        ("./*.md"      , "./.md"                ),
+        ("./docs/**"   , "./docs/a.txt"         ),
+        ("./docs/**"   , "./docs/a/b/c.txt"     ),
+        ("./**/*.pdf"  , "./a.pdf"              ),
+        ("./**/*.pdf"  , "./a/b/c.pdf"          ),
+        ("./file?.txt" , "./file1.txt"          ),
+        ("./[ab].txt"  , "./a.txt"              ),
+        ("./[!ab].txt" , "./c.txt"              ),
+        ("./[0-9].txt" , "./7.txt"              ),
+        ("./a+b(c).txt", "./a+b(c).txt"         ),
+        ("./[a.txt"    , "./[a.txt"             ),
+    ];
+    for &(glob, path) in &data_set {
+        assert!(glob_to_regex(glob).unwrap().is_match(path), "{} should match {}", glob, path);
+    }
+}
+#[test]
+fn glob_to_regex_should_not_match() {
+    let data_set = [
+        ("./*.md"     , "./docs/README.md"),
+        ("./*.md"     , "./README.mdx"    ),
+        ("./docs/**"  , "./docs"          ),
+        ("./docs/**"  , "./docs2/a.txt"   ),
+        ("./**/*.pdf" , "./a.pdf.txt"     ),
+        ("./file?.txt", "./file10.txt"    ),
+        ("./file?.txt", "./file/.txt"     ),
+        ("./[ab].txt" , "./c.txt"         ),
+        ("./[!ab].txt", "./a.txt"         ),
+    ];
+    for &(glob, path) in &data_set {
+        assert!(!glob_to_regex(glob).unwrap().is_match(path), "{} should not match {}", glob, path);
+    }
+}
+
+#[cfg(test)]
+fn root_ignore_from<S: AsRef<str>>(contents: S) -> Ignore {
+    Ignore::from_contents(Ignore::path(), MAIN_DIR.to_string(), contents)
+}
+
+#[test]
+fn normalize_should_supply_prefix_and_keep_pin_and_negation() {
+    let data_set = [
+        ("./a"         , "a"          , "."    ),
+        ("./a"         , "./a/"       , "."    ),
+        ("!./a"        , "!a"         , "."    ),
+        ("!./a/**/*.md", "!a/**/*.md/", "."    ),
+        ("./b/a"       , "a"          , "./b"  ),
+        ("./b/c/*.md"  , "./*.md"     , "./b/c"),
+        ("!./b/*.tmp"  , "!*.tmp"     , "./b"  ),
+        ("/a"          , "/a"         , "./b"  ),
+        ("+./a"        , "+a/"        , "."    ),
+        ("+!./b/a"     , "+!a"        , "./b"  ),
+    ];
+    for &(correct, input, base) in &data_set {
+        assert_eq!(correct.to_string(), normalize(input, base));
+    }
+}
+
+#[test]
+fn is_ignored_should_follow_last_matching_rule() {
+    let contents = "./assets\n!./assets/**/*.tmp\n# comment\n./assets/keep/*.tmp\n*.md\n";
+    let rules    = root_ignore_from(contents).to_rules().unwrap();
+
+    let data_set = [
+        (true , "./assets/a.png"        ),
+        (false, "./assets/a.tmp"        ),
+        (false, "./assets/b/c.tmp"      ),
+        (true , "./assets/keep/d.tmp"   ),
+        (true , "./README.md"           ),
+        (false, "./assets2/a.png"       ),
+        (false, "./docs/README.md"      ),
+    ];
+    for &(correct, path) in &data_set {
+        assert_eq!(correct, rules.is_ignored(path), "{}", path);
+    }
+}
+
+#[test]
+fn from_contents_should_preserve_comments_blank_lines_and_ordering() {
+    let contents = "# Documents\n./b\n\n  # Temporary files\n!b/*.tmp\n./a\n";
+
+    let ignore = root_ignore_from(contents);
+    assert_eq!(vec!["./b", "!./b/*.tmp", "./a"], ignore.entries());
+    assert_eq!(contents.to_string(), ignore.to_string());
+}
+
+#[test]
+fn add_should_append_new_entries_in_order() {
+    let ignore = root_ignore_from("# Keep\n./b\n");
+
+    let ignore = ignore.add(&vec!["*.md".to_string(), "!b/*.tmp".to_string(), "./b".to_string()]);
+    assert_eq!(vec!["./b", "./*.md", "!./b/*.tmp"], ignore.entries());
+    assert_eq!("# Keep\n./b\n./*.md\n!./b/*.tmp\n".to_string(), ignore.to_string());
+}
+
 #[test]
 fn remove_should_remove_specified_entries() {
-    let e1 = "./a"  .to_string();
-    let e2 = "./b"  .to_string();
-    let e3 = "./c/d".to_string();
-    let e4 = "./c/e".to_string();
-
-    let mut entries = BTreeSet::new();
-    entries.insert(e1.clone());
-    entries.insert(e2.clone());
-    entries.insert(e3.clone());
-    entries.insert(e4.clone());
-
-    let ignore = Ignore::_new(entries.clone());
-
-    entries.remove(&e1);
-    let ignore = ignore.remove(&vec![e1]);
-    assert_eq!(&entries, &ignore.entries);
-
-    entries.remove(&e3);
-    entries.remove(&e4);
-    let ignore = ignore.remove(&vec![e3, e4]);
-    assert_eq!(&entries, &ignore.entries);
+    let ignore = root_ignore_from("# A\n./a\n\n# B\nb\n./c/d\n!./c/e\n");
+
+    let ignore = ignore.remove(&vec!["./a".to_string()]);
+    assert_eq!(vec!["./b", "./c/d", "!./c/e"], ignore.entries());
+
+    let ignore = ignore.remove(&vec!["c/d".to_string(), "!./c/e".to_string()]);
+    assert_eq!(vec!["./b"], ignore.entries());
+    assert_eq!("# A\n\n# B\nb\n".to_string(), ignore.to_string());
+}
+
+#[test]
+fn remove_should_remove_pinned_entries_as_well() {
+    let ignore = root_ignore_from("+./a\n./b\n+!./c\n");
+
+    let ignore = ignore.remove(&vec!["a".to_string(), "+b".to_string(), "!c".to_string()]);
+    assert!(ignore.entries().is_empty());
+}
+
+#[test]
+fn refresh_should_remove_dead_entries_except_pinned_ones() {
+    let ignore = root_ignore_from("# Generated\n+./non_existing_file\n./non_existing_file\n./src\n*.md\n");
+
+    let ignore = ignore.refresh();
+    assert_eq!(vec!["+./non_existing_file", "./src", "./*.md"], ignore.entries());
+    assert_eq!("# Generated\n+./non_existing_file\n./src\n*.md\n".to_string(), ignore.to_string());
+}
+
+#[test]
+fn hand_written_globs_should_match_without_current_dir_prefix() {
+    let rules = root_ignore_from("*.md\ndocs/*.txt\n**/*.log\nbuild/\n[ab].tmp\n").to_rules().unwrap();
+
+    let data_set = [
+        (true , "./README.md"       ),
+        (true , "./docs/a.txt"      ),
+        (true , "./log/today.log"   ),
+        (true , "./build/out.o"     ),
+        (true , "./a.tmp"           ),
+        (false, "./c.tmp"           ),
+        (false, "./docs/sub/b.txt"  ),
+        // Unlike ".gitignore", a pattern without "/" is anchored to current directory as well.
+        (false, "./sub/x.md"        ),
+        (true , "./sub/x.log"       ),
+    ];
+    for &(correct, path) in &data_set {
+        assert_eq!(correct, rules.is_ignored(path), "{}", path);
+    }
+}
+
+#[test]
+fn pinned_rules_should_match_like_others() {
+    let rules = root_ignore_from("+./assets\n+!./assets/*.tmp\n").to_rules().unwrap();
+
+    assert!( rules.is_ignored("./assets/a.png"));
+    assert!(!rules.is_ignored("./assets/a.tmp"));
+    assert_eq!("!./assets/*.tmp", rules.last_match("./assets/a.tmp").unwrap().entry());
+}
+
+#[test]
+fn regex_rules_should_match_paths_relative_to_its_directory() {
+    let mut rules = root_ignore_from("re:^\\./exports/\\d{8}_final\\.csv$\n").to_rules().unwrap();
+    let local     = Ignore::from_contents("./sub/.kicignore", "./sub".to_string(), "re:^\\./\\d+\\.tmp$\n").to_rules().unwrap();
+    rules.rules.extend(local.rules);
+
+    assert!( rules.is_ignored("./exports/20160517_final.csv"));
+    assert!(!rules.is_ignored("./exports/2016-05-17_final.csv"));
+    assert!(!rules.is_ignored("./exports/20160517_final.csv.bak"));
+    assert!( rules.is_ignored("./sub/123.tmp"));
+    assert!(!rules.is_ignored("./sub/dir/123.tmp"));
+    assert!(!rules.is_ignored("./123.tmp"));
+}
+
+#[test]
+fn to_rules_should_return_err_with_line_number() {
+    let message = root_ignore_from("./a\n# comment\nre:^(unclosed$\n")
+        .to_rules()
+        .unwrap_err()
+        .to_string();
+    assert!(message.contains("\"re:^(unclosed$\""), "{}", message);
+    assert!(message.contains("line 3"), "{}", message);
+
+    assert!(root_ignore_from("./[z-a]\n").to_rules().is_err());
+}
+
+#[test]
+fn local_rules_should_be_relative_to_its_directory_and_override_root_rules() {
+    let mut rules = root_ignore_from("./sub\n").to_rules().unwrap();
+    let local     = Ignore::from_contents("./sub/.kicignore", "./sub".to_string(), "# Logs\n!*.log\n").to_rules().unwrap();
+    rules.rules.extend(local.rules);
+
+    assert!( rules.is_ignored("./sub/a.txt"));
+    assert!(!rules.is_ignored("./sub/a.log"));
+    assert!(!rules.is_ignored("./a.log"));
+
+    let rule = rules.last_match("./sub/a.log").unwrap();
+    assert_eq!("!./sub/*.log", rule.entry());
+    assert_eq!("\"./sub/.kicignore\" line 2", rule.origin());
+}
+
+#[test]
+fn may_unignore_in_should_check_negated_rules_under_directory() {
+    let rules = root_ignore_from("./a\n./b\n!./b/c/*.tmp\n!./b/f\n").to_rules().unwrap();
+
+    assert!(!rules.may_unignore_in("./a"));
+    assert!( rules.may_unignore_in("./b"));
+    assert!( rules.may_unignore_in("./b/c"));
+    assert!( rules.may_unignore_in("./b/f"));
+    assert!(!rules.may_unignore_in("./b/e"));
+
+    let rules = root_ignore_from("./a\n!re:^\\./d/.*\\.log$\n").to_rules().unwrap();
+    assert!( rules.may_unignore_in("./a"));
 }
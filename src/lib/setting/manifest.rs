@@ -0,0 +1,272 @@
+extern crate chrono;
+extern crate toml;
+
+use self::chrono::{DateTime, Local, UTC};
+use self::chrono::offset::TimeZone;
+use self::toml::Value as Toml;
+
+use error::CliError;
+use lib::fs::Attributes;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Error as IoError, Read, Write};
+use std::path::Path;
+
+
+pub const MANIFEST_FILE_NAME: &'static str = "manifest.toml";
+
+const DATETIME_FORMAT: &'static str = "%Y-%m-%dT%H:%M:%SZ";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Dust,
+    EmptyDir,
+}
+
+impl Kind {
+    fn table_name(&self) -> &str {
+        match *self {
+            Kind::Dust     => "dusts",
+            Kind::EmptyDir => "empty_dirs",
+        }
+    }
+}
+
+
+// One entry of "manifest.toml" in each box.
+// "run" identifies the "sweep" which moved it, so that the run can be undone.
+// "destination" is relative to the box, and "hash" is FNV-1a (64 bit) of the contents of a regular file copied across file systems.
+// "xattrs" are like "user.name=<value in hex>". Owners and "atime" are absent in records of older versions,
+// and all the metadata is absent if it could not be read.
+#[derive(Debug, PartialEq)]
+pub struct Record {
+    pub kind       : Kind,
+    pub run        : String,
+    pub origin     : String,
+    pub destination: String,
+    pub size       : u64,
+    pub mtime      : String,
+    pub atime      : String,
+    pub mode       : String,
+    pub uid        : Option<u32>,
+    pub gid        : Option<u32>,
+    pub xattrs     : Vec<String>,
+    pub hash       : Option<String>,
+    pub swept_at   : String,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<String>>()
+        .concat()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+        .collect()
+}
+
+fn to_datetime_string(secs: i64) -> String {
+    UTC.timestamp(secs, 0).format(DATETIME_FORMAT).to_string()
+}
+
+fn from_datetime_string(datetime: &str) -> Option<i64> {
+    UTC.datetime_from_str(datetime, DATETIME_FORMAT).ok().map(|d| d.timestamp())
+}
+
+impl Record {
+    pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(kind: Kind, run: &str, origin: P, destination: Q, size: u64, attributes: Option<&Attributes>, hash: Option<u64>, swept_at: &DateTime<Local>) -> Self {
+        Record {
+            kind       : kind,
+            run        : run.to_string(),
+            origin     : origin.as_ref().to_string_lossy().into_owned(),
+            destination: destination.as_ref().to_string_lossy().into_owned(),
+            size       : if kind == Kind::Dust { size } else { 0 },
+            mtime      : attributes.map_or(String::new(), |a| to_datetime_string(a.mtime)),
+            atime      : attributes.map_or(String::new(), |a| to_datetime_string(a.atime)),
+            mode       : attributes.map_or(String::new(), |a| format!("{:04o}", a.mode)),
+            uid        : attributes.map(|a| a.uid),
+            gid        : attributes.map(|a| a.gid),
+            xattrs     : attributes.map_or(Vec::new(), |a| a.xattrs.iter().map(|&(ref n, ref v)| format!("{}={}", n, to_hex(v))).collect()),
+            hash       : hash.map(|h| format!("{:016x}", h)),
+            swept_at   : swept_at.with_timezone(&UTC).format(DATETIME_FORMAT).to_string(),
+        }
+    }
+
+    // "None" means that the record is too old to put back the metadata.
+    pub fn attributes(&self) -> Option<Attributes> {
+        let xattrs = self.xattrs
+            .iter()
+            .filter_map(|x| x.rfind('=').and_then(|i| from_hex(&x[i + 1..]).map(|v| (x[..i].to_string(), v))))
+            .collect::<Vec<(String, Vec<u8>)>>();
+
+        match (u32::from_str_radix(&self.mode, 8), self.uid, self.gid, from_datetime_string(&self.atime), from_datetime_string(&self.mtime)) {
+            (Ok(mode), Some(uid), Some(gid), Some(atime), Some(mtime)) => Some(Attributes {
+                mode  : mode,
+                uid   : uid,
+                gid   : gid,
+                atime : atime,
+                mtime : mtime,
+                xattrs: xattrs,
+            }),
+            _ => None,
+        }
+    }
+
+    fn from_toml(kind: Kind, toml: &Toml) -> Option<Self> {
+        let string   = |key: &str| toml.lookup(key).and_then(Toml::as_str).map(|s| s.to_string());
+        let datetime = |key: &str| toml.lookup(key).and_then(Toml::as_datetime).map(|s| s.to_string());
+        let id       = |key: &str| toml.lookup(key).and_then(Toml::as_integer).map(|i| i as u32);
+
+        Some(Record {
+            kind       : kind,
+            run        : string("run").unwrap_or(String::new()),
+            origin     : match string("origin")      { Some(s) => s, None => return None },
+            destination: match string("destination") { Some(s) => s, None => return None },
+            size       : toml.lookup("size").and_then(Toml::as_integer).unwrap_or(0) as u64,
+            mtime      : datetime("mtime").unwrap_or(String::new()),
+            atime      : datetime("atime").unwrap_or(String::new()),
+            mode       : string("mode").unwrap_or(String::new()),
+            uid        : id("uid"),
+            gid        : id("gid"),
+            xattrs     : toml.lookup("xattrs").and_then(Toml::as_slice).map_or(Vec::new(), |x| x.iter().filter_map(Toml::as_str).map(|x| x.to_string()).collect()),
+            hash       : string("hash"),
+            swept_at   : datetime("swept_at").unwrap_or(String::new()),
+        })
+    }
+
+    // Each record is an element of the array of tables, so that it can be appended to the file.
+    pub fn to_toml_string(&self) -> String {
+        let mut table = BTreeMap::new();
+        table.insert("run"        .to_string(), Toml::String(self.run.clone()));
+        table.insert("origin"     .to_string(), Toml::String(self.origin.clone()));
+        table.insert("destination".to_string(), Toml::String(self.destination.clone()));
+        table.insert("size"       .to_string(), Toml::Integer(self.size as i64));
+        table.insert("swept_at"   .to_string(), Toml::Datetime(self.swept_at.clone()));
+        if !self.mtime.is_empty() {
+            table.insert("mtime".to_string(), Toml::Datetime(self.mtime.clone()));
+        }
+        if !self.mode.is_empty() {
+            table.insert("mode".to_string(), Toml::String(self.mode.clone()));
+        }
+        if !self.atime.is_empty() {
+            table.insert("atime".to_string(), Toml::Datetime(self.atime.clone()));
+        }
+        if let (Some(uid), Some(gid)) = (self.uid, self.gid) {
+            table.insert("uid".to_string(), Toml::Integer(uid as i64));
+            table.insert("gid".to_string(), Toml::Integer(gid as i64));
+        }
+        if !self.xattrs.is_empty() {
+            table.insert("xattrs".to_string(), Toml::Array(self.xattrs.iter().map(|x| Toml::String(x.clone())).collect()));
+        }
+        if let Some(ref hash) = self.hash {
+            table.insert("hash".to_string(), Toml::String(hash.clone()));
+        }
+
+        let mut root = BTreeMap::new();
+        root.insert(self.kind.table_name().to_string(), Toml::Array(vec![Toml::Table(table)]));
+
+        Toml::Table(root).to_string()
+    }
+}
+
+
+pub fn read_records<P: AsRef<Path>>(path: P) -> Result<Vec<Record>, CliError> {
+    let mut f = try!(File::open(path));
+
+    let mut contents = String::new();
+    try!(f.read_to_string(&mut contents));
+
+    let result = contents
+        .parse::<Toml>()
+        .map_err(|e: Vec<_>| e.into_iter().next());
+    let toml = match result {
+        Ok(toml)    => toml,
+        Err(option) => match option {
+            Some(e) => return Err(From::from(e)),
+            None    => unreachable!("Evil thing will occur in toml-rs!!"),
+        },
+    };
+
+    let mut records = Vec::new();
+    for kind in vec![Kind::Dust, Kind::EmptyDir] {
+        if let Some(tables) = toml.lookup(kind.table_name()).and_then(Toml::as_slice) {
+            records.extend(tables.iter().filter_map(|t| Record::from_toml(kind, t)));
+        }
+    }
+
+    Ok(records)
+}
+
+pub fn write_records<P: AsRef<Path>>(path: P, records: &Vec<Record>) -> Result<(), IoError> {
+    let contents = records
+        .iter()
+        .fold(String::new(), |contents, record| contents + &record.to_toml_string());
+
+    let mut f = try!(File::create(path));
+    f.write_all(contents.as_bytes())
+}
+
+
+#[test]
+fn to_toml_string_should_return_array_of_tables() {
+    let record = Record {
+        kind       : Kind::Dust,
+        run        : "20160517130506-1234".to_string(),
+        origin     : "./dir/report.pdf".to_string(),
+        destination: "dusts/dir/report.pdf~1".to_string(),
+        size       : 1024,
+        mtime      : "2016-05-16T01:02:03Z".to_string(),
+        atime      : "2016-05-16T04:05:06Z".to_string(),
+        mode       : "0644".to_string(),
+        uid        : Some(1000),
+        gid        : Some(100),
+        xattrs     : vec!["user.origin=6b6963".to_string()],
+        hash       : Some("cbf29ce484222325".to_string()),
+        swept_at   : "2016-05-17T04:05:06Z".to_string(),
+    };
+
+    let expected = r#"
+[[dusts]]
+atime = 2016-05-16T04:05:06Z
+destination = "dusts/dir/report.pdf~1"
+gid = 100
+hash = "cbf29ce484222325"
+mode = "0644"
+mtime = 2016-05-16T01:02:03Z
+origin = "./dir/report.pdf"
+run = "20160517130506-1234"
+size = 1024
+swept_at = 2016-05-17T04:05:06Z
+uid = 1000
+xattrs = ["user.origin=6b6963"]
+"#;
+    assert_eq!(expected, record.to_toml_string());
+
+    let parsed = record.to_toml_string().parse::<Toml>().unwrap();
+    assert_eq!(Some(record), Record::from_toml(Kind::Dust, &parsed.lookup("dusts").unwrap().as_slice().unwrap()[0]));
+}
+
+#[test]
+fn attributes_should_be_restored_from_record() {
+    let attributes = Attributes { mode: 0o4755, uid: 0, gid: 0, atime: 1463457906, mtime: 1463360523, xattrs: vec![("user.a=b".to_string(), vec![0, 255])] };
+    let record     = Record::new(Kind::Dust, "run", "./a", "dusts/a", 1, Some(&attributes), None, &Local::now());
+
+    assert_eq!("4755", record.mode);
+    assert_eq!(vec!["user.a=b=00ff".to_string()], record.xattrs);
+    assert_eq!(Some(attributes), record.attributes());
+
+    let old = Record { uid: None, gid: None, atime: String::new(), ..record };
+    assert_eq!(None, old.attributes());
+
+    let unread = Record::new(Kind::Dust, "run", "./a", "dusts/a", 1, None, None, &Local::now());
+    assert_eq!(None, unread.attributes());
+    assert!(!unread.to_toml_string().contains("mode"));
+}
@@ -1,6 +1,7 @@
 mod config;
 mod cron;
 mod ignore;
+mod manifest;
 mod storage;
 
 use constant::WORKING_DIR_NAME;
@@ -11,7 +12,7 @@ use std::path::{Path, PathBuf};
 
 pub use self::cron::Cron;
 pub use self::config::{Config, ConfigKey};
-pub use self::ignore::Ignore;
+pub use self::ignore::{Ignore, IgnoreRules};
 pub use self::storage::Storage;
 
 
@@ -4,18 +4,34 @@ use self::chrono::{DateTime, Duration, Local};
 use self::chrono::offset::TimeZone;
 
 use constant::STORAGE_DIR_NAME;
+use error::CliError;
 use lib::fs::*;
 use lib::io::*;
+use lib::rule::Buckets;
+use super::manifest::{self, Kind, MANIFEST_FILE_NAME, Record};
 use std::borrow::Borrow;
 use std::collections::BTreeSet;
+use std::fmt::Display;
 use std::fs::{self, OpenOptions};
 use std::io::{Error as IoError, ErrorKind as IoErrorKind, Write};
 use std::path::{Path, PathBuf};
+use std::process;
+
+
+// A dust which is still in a box. "attributes" are absent if the box has no record of them.
+#[derive(Debug)]
+pub struct SweptDust {
+    pub date      : String,
+    pub origin    : String,
+    pub path      : PathBuf,
+    pub attributes: Option<Attributes>,
+}
 
 
 pub struct Storage {
     now     : DateTime<Local>,
     date    : String,
+    run     : String,
     log_file: String,
     indeed  : bool,
 }
@@ -36,10 +52,181 @@ impl Storage {
     }
 
 
+    pub fn box_exists<S: AsRef<str>>(date: S) -> bool {
+        path_buf![Self::path(), date.as_ref()].is_dir()
+    }
+
+    fn dates() -> Result<Vec<String>, IoError> {
+        let mut dates = try!(la(Self::path()))
+            .into_iter()
+            .filter(|date| Local.datetime_from_str(format!("{} 00:00:00", date).as_ref(), "%Y-%m-%d %H:%M:%S").is_ok())
+            .collect::<Vec<String>>();
+        dates.sort();
+        dates.reverse();
+
+        Ok(dates)
+    }
+
+    fn swept_dusts_in_box(date: String) -> Result<Vec<SweptDust>, CliError> {
+        let path_to_box      = path_buf![Self::path(), &date];
+        let path_to_manifest = path_buf![&path_to_box, MANIFEST_FILE_NAME];
+        let path_to_dust_box = path_buf![&path_to_box, "dusts"];
+
+        // Boxes created before "manifest.toml" was introduced have only "dusts".
+        let mut dusts = if path_to_manifest.is_file() {
+            try!(manifest::read_records(path_to_manifest))
+                .into_iter()
+                .filter(|r| r.kind == Kind::Dust)
+                .map(|r| SweptDust { date: date.clone(), attributes: r.attributes(), path: path_buf![&path_to_box, &r.destination], origin: r.origin })
+                .collect::<Vec<SweptDust>>()
+        } else {
+            walk_dir(&path_to_dust_box, &HiddenPolicy::new(true, Vec::new()))
+                .into_iter()
+                .filter_map(|p| Path::new(&p).strip_prefix(&path_to_dust_box).ok().map(|o| format!("./{}", o.display())))
+                .map(|o| SweptDust { date: date.clone(), path: path_buf![&path_to_dust_box, &o], origin: o, attributes: None })
+                .collect::<Vec<SweptDust>>()
+        };
+        dusts.reverse();
+
+        Ok(dusts.into_iter().filter(|d| fs::symlink_metadata(&d.path).is_ok()).collect())
+    }
+
+    // The newest box comes first, and the latest version comes first in each box.
+    pub fn swept_dusts(date: Option<&String>) -> Result<Vec<SweptDust>, CliError> {
+        let dates = match date {
+            Some(d) => vec![d.clone()],
+            None    => try!(Self::dates()),
+        };
+
+        let mut dusts = Vec::new();
+        for date in dates {
+            dusts.extend(try!(Self::swept_dusts_in_box(date)));
+        }
+
+        Ok(dusts)
+    }
+
+
+    // Remove directories in the box which have become empty, from "path" up to "dusts" or "buckets".
+    fn remove_empty_ancestors<P: AsRef<Path>>(path: P, path_to_box: &Path) {
+        let mut path = path.as_ref().to_path_buf();
+        while path.parent().map_or(false, |p| p.starts_with(path_to_box) && p != path_to_box) && fs::remove_dir(&path).is_ok() {
+            path.pop();
+        }
+    }
+
+    // Forget a "dust" which has been moved out of the box, so that neither "restore" nor "undo" finds it again.
+    pub fn forget_dust(dust: &SweptDust) -> Result<(), CliError> {
+        let path_to_box      = path_buf![Self::path(), &dust.date];
+        let path_to_manifest = path_buf![&path_to_box, MANIFEST_FILE_NAME];
+
+        if path_to_manifest.is_file() {
+            let records = try!(manifest::read_records(&path_to_manifest))
+                .into_iter()
+                .filter(|r| r.kind != Kind::Dust || path_buf![&path_to_box, &r.destination] != dust.path)
+                .collect::<Vec<Record>>();
+            try!(manifest::write_records(&path_to_manifest, &records));
+        }
+        if let Some(parent) = dust.path.parent() {
+            Self::remove_empty_ancestors(parent, &path_to_box);
+        }
+
+        Ok(())
+    }
+
+    fn undo_record(path_to_box: &Path, record: &Record) -> Result<bool, IoError> {
+        let from = path_buf![path_to_box, &record.destination];
+        let to   = PathBuf::from(&record.origin);
+
+        print_with_tag(Tag::Info, format!("  => \"{}\"", record.origin));
+
+        match record.kind {
+            Kind::Dust => {
+                if fs::symlink_metadata(&from).is_err() {
+                    print_with_tag(Tag::Notice, format!("\"{}\" has already gone from the box", record.destination));
+                    return Ok(true);
+                }
+                if fs::symlink_metadata(&to).is_ok() {
+                    print_with_tag(Tag::Notice, format!("\"{}\" is left in the box because \"{}\" already exists", record.destination, record.origin));
+                    return Ok(false);
+                }
+
+                if let Some(parent) = to.parent() {
+                    try!(fs::create_dir_all(parent));
+                }
+                match fs::rename(&from, &to) {
+                    Err(ref e) if is_cross_device(e) => try!(copy_and_remove(&from, &to)),
+                    result                           => try!(result),
+                };
+                if let Some(parent) = from.parent() {
+                    Self::remove_empty_ancestors(parent, path_to_box);
+                }
+            },
+            Kind::EmptyDir => {
+                try!(fs::create_dir_all(&to));
+                Self::remove_empty_ancestors(&from, path_to_box);
+            },
+        };
+
+        if let Some(attributes) = record.attributes() {
+            let lost = try!(attributes.put_back(&to));
+            if !lost.is_empty() {
+                print_with_tag(Tag::Notice, format!("Cannot put back {} of \"{}\"", lost.join(", "), record.origin));
+            }
+        }
+
+        Ok(true)
+    }
+
+    // Move back what the run moved. The latest run is chosen if "run" is not specified.
+    pub fn undo(run: Option<&String>) -> Result<(), CliError> {
+        for date in try!(Self::dates()) {
+            let path_to_box      = path_buf![Self::path(), &date];
+            let path_to_manifest = path_buf![&path_to_box, MANIFEST_FILE_NAME];
+            if !path_to_manifest.is_file() {
+                continue;
+            }
+
+            let records = try!(manifest::read_records(&path_to_manifest));
+            let run     = match run {
+                Some(r) => r.clone(),
+                None    => match records.iter().map(|r| &r.run).max() {
+                    Some(r) => r.clone(),
+                    None    => continue,
+                },
+            };
+            if !records.iter().any(|r| r.run == run) {
+                continue;
+            }
+
+            print_with_tag(Tag::Info, format!("Undo \"{}\" in \"{}\" box", run, date));
+
+            let (targets, mut remains): (Vec<Record>, Vec<Record>) = records
+                .into_iter()
+                .partition(|r| r.run == run);
+
+            // Empty directories were moved after files, so reverse the order.
+            for record in targets.into_iter().rev() {
+                if !try!(Self::undo_record(&path_to_box, &record)) {
+                    remains.push(record);
+                }
+            }
+
+            try!(manifest::write_records(path_to_manifest, &remains));
+            return Ok(());
+        }
+
+        print_with_tag(Tag::Notice, "There is no run of \"sweep\" to be undone");
+        Ok(())
+    }
+
+
     pub fn new<S: AsRef<str>>(file_name: S, indeed: bool) -> Self {
         let now  = Local::now();
         let date = now.format("%Y-%m-%d").to_string();
-        Storage { now: now, date: date, log_file: format!("{}.log", file_name.as_ref()), indeed: indeed }
+        // Several runs can share a box, so that each run is identified by its start time and process.
+        let run  = format!("{}-{}", now.format("%Y%m%d%H%M%S"), process::id());
+        Storage { now: now, date: date, run: run, log_file: format!("{}.log", file_name.as_ref()), indeed: indeed }
     }
 
 
@@ -51,10 +238,19 @@ impl Storage {
         path_buf![self.path_to_box(), "dusts"]
     }
 
+    // Buckets are beside "dusts", not to be mixed up with directories swept into "dusts".
+    fn path_to_bucket<S: AsRef<str>>(&self, name: S) -> PathBuf {
+        path_buf![self.path_to_box(), "buckets", name.as_ref()]
+    }
+
     fn path_to_log(&self) -> PathBuf {
         path_buf![self.path_to_box(), &self.log_file]
     }
 
+    fn path_to_manifest(&self) -> PathBuf {
+        path_buf![self.path_to_box(), MANIFEST_FILE_NAME]
+    }
+
     pub fn create_box(&self) -> Result<(), IoError> {
         print_with_tag(Tag::Info, format!(r#"Create "{}" directory in "{}""#, self.date, Self::path().display()));
 
@@ -62,11 +258,13 @@ impl Storage {
 
         print_with_tag(Tag::Info, format!(r#"Create "{}" file in "{}""#, self.log_file, self.path_to_box().display()));
 
-        self.write_log(self.start_mark_for_log()).map(|_| ())
+        try!(self.write_log(self.start_mark_for_log()));
+        self.write_log(format!("Run \"{}\"", self.run)).map(|_| ())
     }
 
 
-    pub fn squeeze_dusts<P: AsRef<Path>, V: Borrow<Vec<P>>>(&self, paths_to_dust: V) -> Result<(), IoError> {
+    // The original path is kept under the bucket, and "manifest.toml" records it for "restore" and "undo".
+    pub fn squeeze_dusts<P: AsRef<Path>, V: Borrow<Vec<P>>>(&self, paths_to_dust: V, buckets: &Buckets) -> Result<(), IoError> {
         let path_to_dust_box = self.path_to_dust_box();
 
         let addition = if self.indeed { "" } else { " (dry-run mode)" };
@@ -84,19 +282,68 @@ impl Storage {
                 None    => unreachable!("Cannot get base name from path!!"),
             };
 
-            let to = path_buf![&path_to_dust_box, target_base];
+            // Drop "." of "dust"s in current directory, which "create_dir_all" cannot create in a new bucket.
+            let bucket = buckets.bucket_of(path_to_dust);
+            let to_dir = match bucket {
+                Some(b) => path_buf![self.path_to_bucket(b), target_base],
+                None    => path_buf![&path_to_dust_box, target_base],
+            }.components().collect::<PathBuf>();
+            let to     = available_path(path_buf![&to_dir, target_file]);
 
             let message = format!("  => \"{}\"", path_to_dust.display());
             try!(self.print_and_log(message));
 
+            if let Some(b) = bucket {
+                try!(self.print_and_log(format!("     Put in \"{}\" bucket", b)));
+            }
+
+            // Keep all versions of the file swept on the same day.
+            if let Some(name) = to.file_name().filter(|&n| n != target_file) {
+                try!(self.print_and_log(format!("     Save as \"{}\" because the name has already been used", name.to_string_lossy())));
+            }
+
             if self.indeed {
-                try!(fs::create_dir_all(&to));
+                try!(fs::create_dir_all(&to_dir));
+
+                // A "dust" which cannot be read can still be moved, so that nothing read here stops the run.
+                let size       = fs::symlink_metadata(path_to_dust).map(|m| m.len()).unwrap_or(0);
+                let attributes = match Attributes::read(path_to_dust) {
+                    Ok(a)  => Some(a),
+                    Err(e) => {
+                        try!(self.print_and_log(format!("     Cannot read its metadata ({}), so it is not recorded", e)));
+                        None
+                    },
+                };
 
-                // forcedly overwrite if the file exists with same name.
-                match fs::rename(path_to_dust, path_buf![to, target_file]) {
-                    Ok(_)  => (),
+                let mut copied = false;
+                let result     = match fs::rename(path_to_dust, &to) {
+                    Err(ref e) if is_cross_device(e) => {
+                        try!(self.print_and_log("     Copy across file systems and verify it"));
+                        copied = true;
+                        copy_and_remove(path_to_dust, &to)
+                    },
+                    result => result,
+                };
+                match result {
+                    Ok(_)  => {
+                        // A copy may not keep everything, but "restore" and "undo" put it back from the record.
+                        if let Some(ref attributes) = attributes {
+                            let lost = try!(attributes.put_back(&to));
+                            if !lost.is_empty() {
+                                try!(self.print_and_log(format!("     Cannot keep {} in the box, but they are recorded in \"{}\"", lost.join(", "), MANIFEST_FILE_NAME)));
+                            }
+                        }
+
+                        // Only a copy is worth the hash, since a rename never changes the contents.
+                        let hash   = if copied && to.is_file() { hash_file(&to).ok() } else { None };
+                        let record = self.record(Kind::Dust, path_to_dust, &to, size, attributes.as_ref(), hash);
+                        try!(self.write_manifest(record));
+                    },
                     Err(e) => match e.kind() {
                         IoErrorKind::PermissionDenied => try!(self.print_and_log("     Interrupted for permission")),
+                        IoErrorKind::InvalidData      => try!(self.print_and_log("     Interrupted for failure of verification")),
+                        // e.g. removed by others after it was found
+                        IoErrorKind::NotFound         => try!(self.print_and_log("     Interrupted because it has gone")),
                         _                             => return Err(e),
                     },
                 };
@@ -106,6 +353,33 @@ impl Storage {
         Ok(())
     }
 
+    pub fn skip_dusts<S: AsRef<str>, D: Display>(&self, skipped: &Vec<(S, D)>) -> Result<(), IoError> {
+        if skipped.is_empty() {
+            return Ok(());
+        }
+
+        try!(self.print_and_log("Skip dusts for the limits"));
+        for &(ref path, ref reason) in skipped {
+            try!(self.print_and_log(format!("  => \"{}\" ({})", path.as_ref(), reason)));
+        }
+
+        Ok(())
+    }
+
+    // Files opened by other processes are left for a later "sweep", not to break the processes.
+    pub fn defer_dusts<S: AsRef<str>>(&self, deferred: &Vec<S>) -> Result<(), IoError> {
+        if deferred.is_empty() {
+            return Ok(());
+        }
+
+        try!(self.print_and_log("Defer dusts opened by running processes"));
+        for path in deferred {
+            try!(self.print_and_log(format!("  => \"{}\" (in use)", path.as_ref())));
+        }
+
+        Ok(())
+    }
+
     pub fn squeeze_empty_dirs<P: AsRef<Path>>(&self, paths_to_dir: BTreeSet<P>) -> Result<(), IoError> {
         let mut paths_to_dir = paths_to_dir
             .iter()
@@ -125,11 +399,22 @@ impl Storage {
             try!(self.print_and_log(message));
 
             if self.indeed {
+                let attributes = Attributes::read(path_to_dir).ok();
                 match fs::remove_dir(path_to_dir) {
-                    Ok(_)  => try!(fs::create_dir_all(path_buf![&path_to_dust_box, path_to_dir])),
+                    Ok(_)  => {
+                        let to = path_buf![&path_to_dust_box, path_to_dir];
+                        try!(fs::create_dir_all(&to));
+
+                        let record = self.record(Kind::EmptyDir, path_to_dir, &to, 0, attributes.as_ref(), None);
+                        try!(self.write_manifest(record));
+                    },
                     Err(e) => match e.kind() {
-                        IoErrorKind::PermissionDenied => try!(self.print_and_log("     Interrupted for permission")),
-                        _                             => return Err(e),
+                        IoErrorKind::PermissionDenied  => try!(self.print_and_log("     Interrupted for permission")),
+                        // e.g. a mount point which appeared after the walk
+                        IoErrorKind::ResourceBusy      => try!(self.print_and_log("     Interrupted because it is in use")),
+                        // e.g. a "dust" in it could not be moved
+                        IoErrorKind::DirectoryNotEmpty => try!(self.print_and_log("     Interrupted because it is not empty")),
+                        _                              => return Err(e),
                     },
                 };
             }
@@ -187,6 +472,27 @@ impl Storage {
         file.write(format!("{}\n", content.as_ref()).as_bytes())
     }
 
+    fn record<P: AsRef<Path>>(&self, kind: Kind, origin: P, to: &Path, size: u64, attributes: Option<&Attributes>, hash: Option<u64>) -> Record {
+        let destination = to
+            .components()
+            .collect::<PathBuf>()
+            .strip_prefix(self.path_to_box())
+            .map(|p| p.to_path_buf())
+            .unwrap_or(to.to_path_buf());
+
+        Record::new(kind, &self.run, origin, destination, size, attributes, hash, &self.now)
+    }
+
+    fn write_manifest(&self, record: Record) -> Result<(), IoError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path_to_manifest());
+        let mut file = try!(file);
+
+        file.write_all(record.to_toml_string().as_bytes())
+    }
+
     fn print_and_log<S: AsRef<str>>(&self, message: S) -> Result<(), IoError> {
         print_with_tag(Tag::Info, message.as_ref());
         self.write_log(message).map(|_| ())